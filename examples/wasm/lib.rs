@@ -0,0 +1,12 @@
+//! In-browser replay inspector built on the `wasm` feature bindings.
+//!
+//! Build the bindings and serve this directory to open `index.html`:
+//!
+//! ```sh
+//! cargo build --example wasm --release --target wasm32-unknown-unknown --features wasm
+//! wasm-bindgen --target web --out-dir examples/wasm/pkg \
+//!     target/wasm32-unknown-unknown/release/examples/wasm.wasm
+//! python3 -m http.server --directory examples/wasm
+//! ```
+
+pub use osu_replay_parser::wasm::parse_replay;
@@ -1,6 +1,9 @@
+use crate::types::Integer;
+use alloc::string::String;
 use thiserror::Error;
 
-#[derive(Error, Debug)]
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+#[allow(clippy::enum_variant_names)]
 pub enum Error {
     #[error("Given path is not a file: {}", path)]
     NotAFile { path: String },
@@ -15,26 +18,73 @@ pub enum Error {
     #[error("Unexpected error while reading the value into buffer")]
     ReadBufferingError,
 
+    #[error("Game version {} isn't a plausible osu! version", version)]
+    UnexpectedGameVersion { version: Integer },
     #[error("Invalid gamemode replay value")]
     InvalidGamemode,
+    #[error("Unknown mod acronym: {}", acronym)]
+    UnknownModAcronym { acronym: String },
     #[error("Unknown error while reading string")]
     CantReadString,
+    #[error("Decoding Windows-1252 strings requires the `encoding` feature")]
+    EncodingFeatureDisabled,
     #[error("The byte value read was unexpected for this field")]
     UnexpectedFullComboValue,
     #[error("Error while reading the first string part")]
     UnexpectedStringValue,
+    #[error("The hit counts contradict the full combo flag")]
+    InconsistentHitCounts,
+    #[error("{} unexpected bytes follow the replay", len)]
+    UnexpectedTrailingBytes { len: usize },
 
+    #[error("The replay file isn't valid base64")]
+    InvalidBase64,
     #[error("Unknown error while decompressing replay data")]
     ReplayDataDecompressError,
     #[error("Unexpected error while loading the lzma encoder")]
     NewLzmaEncoderError,
+    #[error("The replay data can't be compressed within {} bytes", max_bytes)]
+    CompressedSizeExceeded { max_bytes: usize },
+    #[error(
+        "The compressed replay data of {} bytes doesn't fit in the 32-bit length field",
+        length
+    )]
+    ReplayDataTooLarge { length: usize },
 
     #[error("Invalid event string format")]
     InvalidStringFrameFormat,
     #[error("Error while parsing a replay event value")]
     CantParseFrameValue,
+    #[error("Unknown key name")]
+    InvalidKeyName,
+    #[error("Unknown key bits in the keys value: {}", bits)]
+    InvalidKeyBits { bits: Integer },
     #[error("Event value 'x' isn't in the valid range 0 - 512")]
     InvalidFrameValueX,
     #[error("Event value 'y' isn't in the valid range 0 - 384")]
     InvalidFrameValueY,
 }
+
+////////////////////////////////////////////////////////////////////////////////////////////////////
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::string::ToString;
+
+    #[test]
+    fn compare_errors() {
+        let error = Error::NotAReplayFile {
+            file: "replay.txt".to_string(),
+        };
+
+        assert_eq!(error.clone(), error);
+        assert_ne!(
+            error,
+            Error::NotAReplayFile {
+                file: "other.txt".to_string(),
+            }
+        );
+        assert_ne!(error, Error::CantOpenFile);
+    }
+}
@@ -1,6 +1,12 @@
+#![cfg_attr(not(feature = "std"), no_std)]
+
+extern crate alloc;
 extern crate core;
 
-mod error;
+pub mod error;
 pub mod replay;
-mod types;
+pub mod types;
+#[cfg(feature = "std")]
 mod utils;
+#[cfg(feature = "wasm")]
+pub mod wasm;
@@ -0,0 +1,120 @@
+use super::*;
+
+/// Metrics of a play computed in a single pass over its frames (see `Replay::analyze`)
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ReplayAnalysis {
+    pub accuracy: Double,
+    pub grade: Grade,
+    /// Time between the first and the last gameplay frames, in milliseconds
+    pub duration_ms: Long,
+    /// Number of gameplay frames (see `ReplayData::gameplay_frames`)
+    pub frame_count: usize,
+    /// Gameplay frames per second over the duration
+    pub average_fps: f64,
+    /// Number of key-down events (M1 or M2 getting pressed)
+    pub key_presses: usize,
+    /// Total distance travelled by the cursor, in osu! pixels
+    pub cursor_distance: f64,
+    /// Average cursor speed over the duration, in osu! pixels per second
+    pub average_cursor_speed: f64,
+    /// Mean cursor position (see `ReplayData::centroid`)
+    pub centroid: (f32, f32),
+}
+
+impl Replay {
+    /// Compute the accuracy, grade, frame, key and cursor metrics of the play at once,
+    /// going over the frames a single time
+    pub fn analyze(&self) -> ReplayAnalysis {
+        let mut frame_count = 0;
+        let mut window: Option<(Long, Long)> = None;
+        let mut key_presses = 0;
+        let mut cursor_distance = 0.0;
+        let (mut x_sum, mut y_sum) = (0.0, 0.0);
+        let mut previous: Option<&ReplayFrame> = None;
+
+        for (time, frame) in self.replay_data.timed_gameplay_frames() {
+            frame_count += 1;
+            window = Some((window.map_or(time, |(start, _)| start), time));
+
+            let buttons = frame.z & (Keys::M1 | Keys::M2);
+            let previous_buttons =
+                previous.map_or(Keys::empty(), |previous| previous.z & (Keys::M1 | Keys::M2));
+            key_presses += (buttons - previous_buttons).bits().count_ones() as usize;
+
+            if let Some(previous) = previous {
+                let dx = (frame.x - previous.x) as f64;
+                let dy = (frame.y - previous.y) as f64;
+                cursor_distance += dx.hypot(dy);
+            }
+            x_sum += frame.x as f64;
+            y_sum += frame.y as f64;
+            previous = Some(frame);
+        }
+
+        let (start, end) = window.unwrap_or((0, 0));
+        let duration_ms = end - start;
+        let duration_s = duration_ms as f64 / 1000.0;
+        let per_second = |value: f64| {
+            if duration_ms > 0 {
+                value / duration_s
+            } else {
+                0.0
+            }
+        };
+        let centroid = if frame_count == 0 {
+            (0.0, 0.0)
+        } else {
+            let count = frame_count as f64;
+            ((x_sum / count) as f32, (y_sum / count) as f32)
+        };
+
+        ReplayAnalysis {
+            accuracy: self.accuracy(),
+            grade: self.grade(),
+            duration_ms,
+            frame_count,
+            average_fps: per_second(frame_count as f64),
+            key_presses,
+            cursor_distance,
+            average_cursor_speed: per_second(cursor_distance),
+            centroid,
+        }
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////////////////////////
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::utils::sample::sample_replay;
+
+    #[test]
+    fn analyze_replay() {
+        let replay = sample_replay();
+        let (start, end) = replay.play_window();
+
+        let analysis = replay.analyze();
+
+        assert_eq!(analysis.accuracy, replay.accuracy());
+        assert_eq!(analysis.grade, replay.grade());
+        assert_eq!(analysis.duration_ms, end - start);
+        assert_eq!(
+            analysis.frame_count,
+            replay.replay_data.gameplay_frames().len()
+        );
+        assert_eq!(
+            analysis.key_presses,
+            replay
+                .replay_data
+                .clicks_per_second(ReplayData::DEFAULT_CPS_WINDOW_MS)
+                .len()
+        );
+        assert_eq!(analysis.centroid, replay.replay_data.centroid());
+        assert!(analysis.average_fps > 0.0);
+        assert!(analysis.cursor_distance > 0.0);
+        assert!(analysis.average_cursor_speed > 0.0);
+    }
+}
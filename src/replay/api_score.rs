@@ -0,0 +1,95 @@
+use super::*;
+
+/// Values of a score as given by the osu! API (`enabled_mods`, `count300`, `maxcombo`...),
+/// to build a placeholder replay with `Replay::from_score_fields`
+#[derive(Debug, Default, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ApiScore {
+    pub mode: Gamemode,
+    pub beatmap_md5: String,
+    pub username: String,
+    pub count_300: Short,
+    pub count_100: Short,
+    pub count_50: Short,
+    pub count_geki: Short,
+    pub count_katu: Short,
+    pub count_miss: Short,
+    pub score: Integer,
+    pub max_combo: Short,
+    pub perfect: bool,
+    /// Bitmask of the mods
+    pub enabled_mods: Integer,
+    pub date: NaiveDateTime,
+    /// Online score ID, 0 if unknown
+    pub score_id: Long,
+}
+
+impl Replay {
+    /// Build a replay without frames from the values of an osu! API score.
+    /// The game version is the first of `SUPPORTED_VERSION_RANGE` so every value is written,
+    /// and the replay hash is computed (see `compute_replay_hash`).
+    pub fn from_score_fields(score: ApiScore) -> Self {
+        let mut replay = Self {
+            gamemode: score.mode,
+            game_version: *Self::SUPPORTED_VERSION_RANGE.start(),
+            map_hash: score.beatmap_md5,
+            player_name: score.username,
+            number_300s: score.count_300,
+            number_100s: score.count_100,
+            number_50s: score.count_50,
+            number_gekis: score.count_geki,
+            number_katus: score.count_katu,
+            number_misses: score.count_miss,
+            total_score: score.score,
+            greatest_combo: score.max_combo,
+            is_full_combo: score.perfect,
+            mods: score.enabled_mods.into(),
+            play_date: score.date,
+            score_id: score.score_id,
+            ..Self::default()
+        };
+        replay.replay_hash = replay.compute_replay_hash();
+        replay
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////////////////////////
+
+#[cfg(all(test, feature = "std"))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn replay_from_score_fields() {
+        let score = ApiScore {
+            mode: Gamemode::STD,
+            beatmap_md5: "594e0c140ad4b09ab8dfe39fcdce5d4f".to_string(),
+            username: "Sailor SnoW".to_string(),
+            count_300: 594,
+            count_100: 16,
+            count_50: 2,
+            count_geki: 140,
+            count_katu: 12,
+            count_miss: 0,
+            score: 13392443,
+            max_combo: 852,
+            perfect: true,
+            enabled_mods: 8,
+            date: crate::utils::ticks_to_datetime(637691351690000000),
+            score_id: 3760034870,
+        };
+
+        let replay = Replay::from_score_fields(score.clone());
+        let bytes: Vec<u8> = replay.try_into().unwrap();
+        let replay = Replay::from_bytes(&bytes).unwrap();
+
+        assert_eq!(replay.player_name, score.username);
+        assert_eq!(replay.map_hash, score.beatmap_md5);
+        assert_eq!(replay.mods, Mods::HIDDEN);
+        assert_eq!(replay.total_score, 13392443);
+        assert_eq!(replay.play_date, score.date);
+        assert_eq!(replay.score_id, 3760034870);
+        assert!(replay.verify_replay_hash());
+        assert!(replay.replay_data.gameplay_frames().is_empty());
+    }
+}
@@ -0,0 +1,120 @@
+use super::*;
+
+/// Every value of a replay except its replay data, for storing the metadata
+/// separately from the frames (see `Replay::into_parts` and `Replay::assemble`)
+#[derive(Debug, Default, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ReplayHeader {
+    pub gamemode: Gamemode,
+    pub game_version: Integer,
+    pub map_hash: String,
+    pub player_name: String,
+    pub replay_hash: String,
+    pub number_300s: Short,
+    pub number_100s: Short,
+    pub number_50s: Short,
+    pub number_gekis: Short,
+    pub number_katus: Short,
+    pub number_misses: Short,
+    pub total_score: Integer,
+    pub greatest_combo: Short,
+    pub is_full_combo: bool,
+    pub mods: Mods,
+    pub life_bar_graph: LifeBar,
+    pub play_date: NaiveDateTime,
+    pub compressed_length: Integer,
+    pub score_id: Long,
+    pub additional_mod_info: Option<Double>,
+    pub trailing_bytes: Vec<u8>,
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub warnings: Vec<options::ParseWarning>,
+    pub notes: Option<String>,
+}
+
+impl Replay {
+    /// Build a replay from its header and its replay data.
+    /// The compressed length of the replay data is computed when writing the replay.
+    pub fn assemble(header: ReplayHeader, replay_data: ReplayData) -> Self {
+        Self {
+            gamemode: header.gamemode,
+            game_version: header.game_version,
+            map_hash: header.map_hash,
+            player_name: header.player_name,
+            replay_hash: header.replay_hash,
+            number_300s: header.number_300s,
+            number_100s: header.number_100s,
+            number_50s: header.number_50s,
+            number_gekis: header.number_gekis,
+            number_katus: header.number_katus,
+            number_misses: header.number_misses,
+            total_score: header.total_score,
+            greatest_combo: header.greatest_combo,
+            is_full_combo: header.is_full_combo,
+            mods: header.mods,
+            life_bar_graph: header.life_bar_graph,
+            play_date: header.play_date,
+            compressed_length: header.compressed_length,
+            replay_data,
+            score_id: header.score_id,
+            additional_mod_info: header.additional_mod_info,
+            trailing_bytes: header.trailing_bytes,
+            warnings: header.warnings,
+            notes: header.notes,
+        }
+    }
+
+    /// Split the replay into its header and its replay data
+    pub fn into_parts(self) -> (ReplayHeader, ReplayData) {
+        let header = ReplayHeader {
+            gamemode: self.gamemode,
+            game_version: self.game_version,
+            map_hash: self.map_hash,
+            player_name: self.player_name,
+            replay_hash: self.replay_hash,
+            number_300s: self.number_300s,
+            number_100s: self.number_100s,
+            number_50s: self.number_50s,
+            number_gekis: self.number_gekis,
+            number_katus: self.number_katus,
+            number_misses: self.number_misses,
+            total_score: self.total_score,
+            greatest_combo: self.greatest_combo,
+            is_full_combo: self.is_full_combo,
+            mods: self.mods,
+            life_bar_graph: self.life_bar_graph,
+            play_date: self.play_date,
+            compressed_length: self.compressed_length,
+            score_id: self.score_id,
+            additional_mod_info: self.additional_mod_info,
+            trailing_bytes: self.trailing_bytes,
+            warnings: self.warnings,
+            notes: self.notes,
+        };
+
+        (header, self.replay_data)
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////////////////////////
+
+#[cfg(all(test, feature = "std"))]
+mod tests {
+    use super::*;
+
+    use crate::utils::sample::TEST_REPLAY_FILE;
+
+    #[test]
+    fn assemble_replay() {
+        let replay = Replay::open(Path::new(TEST_REPLAY_FILE)).unwrap();
+        let frame_count = replay.replay_data.frames.len();
+        let (header, replay_data) = replay.into_parts();
+
+        let replay = Replay::assemble(header, replay_data);
+        let written: Vec<u8> = replay.try_into().unwrap();
+
+        let replay = Replay::from_bytes(&written).unwrap();
+        assert_eq!(replay.player_name, "Sailor SnoW");
+        assert_eq!(replay.replay_data.frames.len(), frame_count);
+        assert_eq!(replay.replay_data.seed, Some(19290764));
+    }
+}
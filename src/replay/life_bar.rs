@@ -1,11 +1,22 @@
 use crate::error::Error;
-use std::str::FromStr;
+use alloc::format;
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+use core::str::FromStr;
 
 /// Represents parsed data of the life bar graph
-#[derive(Default, Debug)]
+#[derive(Default, Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct LifeBar {
     pub base_time: u32,
     events: Vec<LifeBarEvent>,
+    /// Parsed string with the base time it was parsed with, serialized verbatim
+    /// while the events aren't modified and the base time is unchanged
+    #[cfg_attr(feature = "serde", serde(skip))]
+    original: Option<(u32, String)>,
+    /// Whether the life bar is written as an absent string (see `Replay::strip_life_bar`)
+    #[cfg_attr(feature = "serde", serde(default))]
+    absent: bool,
 }
 
 impl LifeBar {
@@ -17,6 +28,26 @@ impl LifeBar {
         &self.events
     }
 
+    /// Add an event at the end of the life bar graph
+    pub fn push_event(&mut self, event: LifeBarEvent) {
+        self.original = None;
+        self.absent = false;
+        self.events.push(event)
+    }
+
+    /// Life bar without any event, written as an absent string instead of an empty one
+    pub fn absent() -> Self {
+        Self {
+            absent: true,
+            ..Self::default()
+        }
+    }
+
+    /// Whether the life bar is written as an absent string, see `absent`
+    pub fn is_absent(&self) -> bool {
+        self.absent
+    }
+
     pub fn parse(str: &str) -> Result<Self, Error> {
         LifeBar::from_str(str)
     }
@@ -28,6 +59,73 @@ impl LifeBar {
     pub fn delete_bar_data(&mut self) {
         *self = Self::default()
     }
+
+    /// Life value at the given time (in milliseconds), linearly interpolated between the events,
+    /// or `None` if the life bar has no event. The first and last values are held before the
+    /// first event and after the last one.
+    pub fn life_at(&self, time_ms: u32) -> Option<f32> {
+        let after = self.events.iter().position(|event| event.u >= time_ms);
+
+        match after {
+            Some(0) => Some(self.events[0].v),
+            Some(index) => {
+                let (previous, next) = (&self.events[index - 1], &self.events[index]);
+                if next.u <= previous.u {
+                    // unsorted events
+                    return Some(next.v);
+                }
+                let ratio =
+                    time_ms.saturating_sub(previous.u) as f32 / (next.u - previous.u) as f32;
+                Some(previous.v + (next.v - previous.v) * ratio)
+            }
+            None => self.events.last().map(|event| event.v),
+        }
+    }
+
+    /// Life values `(time, life)` every `interval_ms` milliseconds (at least 1) from the first
+    /// event to the last one, interpolated with `life_at`
+    pub fn resample(&self, interval_ms: u32) -> Vec<(u32, f32)> {
+        self.sampled(interval_ms).collect()
+    }
+
+    /// Same as `resample`, yielding the values lazily
+    pub fn sampled(&self, interval_ms: u32) -> impl Iterator<Item = (u32, f32)> + '_ {
+        let times = match (self.events.first(), self.events.last()) {
+            (Some(first), Some(last)) => first.u..=last.u,
+            #[allow(clippy::reversed_empty_ranges)]
+            _ => 1..=0,
+        };
+
+        times
+            .step_by(interval_ms.max(1) as usize)
+            .filter_map(move |time| self.life_at(time).map(|life| (time, life)))
+    }
+
+    /// Whether the events are in time order, as needed by `life_at`.
+    /// The events are kept in the order of the replay file, usually but not always sorted.
+    pub fn is_time_sorted(&self) -> bool {
+        self.events.windows(2).all(|pair| pair[0].u <= pair[1].u)
+    }
+
+    /// Sort the events by time, keeping the order of the events at the same time
+    pub fn sort_by_time(&mut self) {
+        if !self.is_time_sorted() {
+            self.events.sort_by_key(|event| event.u);
+            self.original = None;
+        }
+    }
+
+    /// Clamp the life values into `0.0..=1.0`, fixing the values slightly out of range
+    /// written by osu! because of rounding
+    pub fn clamp_values(&mut self) {
+        for event in self.events.iter_mut() {
+            let clamped = event.v.clamp(0.0, 1.0);
+            if clamped != event.v {
+                event.v = clamped;
+                self.original = None;
+            }
+        }
+    }
 }
 
 impl FromStr for LifeBar {
@@ -39,24 +137,35 @@ impl FromStr for LifeBar {
         let mut events: Vec<LifeBarEvent> = Vec::new();
 
         for event in splitted_events.iter() {
-            if event.len() > 0 && event.contains(',') {
-                match LifeBarEvent::from_str(event) {
-                    Ok(e) => events.push(e),
-                    Err(_) => (),
+            if !event.is_empty() && event.contains(',') {
+                if let Ok(e) = LifeBarEvent::from_str(event) {
+                    events.push(e)
                 }
             }
         }
 
+        let base_time = match splitted_events.first() {
+            Some(base_time) => u32::from_str(base_time).unwrap_or_default(),
+            None => 0,
+        };
+
         Ok(Self {
-            base_time: u32::from_str(splitted_events[0]).unwrap_or_default(),
+            base_time,
             events,
+            original: Some((base_time, s.to_string())),
+            absent: false,
         })
     }
 }
 
 impl From<&LifeBar> for String {
     fn from(life_bar: &LifeBar) -> Self {
-        if life_bar.events().len() == 0 {
+        if let Some((base_time, original)) = &life_bar.original {
+            if *base_time == life_bar.base_time {
+                return original.clone();
+            }
+        }
+        if life_bar.events().is_empty() {
             return String::from("");
         }
 
@@ -72,7 +181,7 @@ impl From<&LifeBar> for String {
             s.push_str(&serialized);
         }
 
-        if s.len() > 0 {
+        if !s.is_empty() {
             s.push('|')
         }
 
@@ -85,6 +194,7 @@ impl From<&LifeBar> for String {
 ////////////////////////////////////////////////////////////////////////////////////////////////////
 
 #[derive(Default, Debug, Copy, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct LifeBarEvent {
     /// time in milliseconds into the song
     pub u: u32,
@@ -131,6 +241,7 @@ impl From<&LifeBarEvent> for String {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use alloc::vec;
 
     // Life Bar Event
 
@@ -168,6 +279,14 @@ mod tests {
         assert_eq!(life_bar.events[1].v, 1.0);
     }
     #[test]
+    fn parse_empty_lifebar() {
+        let life_bar = LifeBar::from_str("").unwrap();
+
+        assert_eq!(life_bar.base_time, 0);
+        assert!(life_bar.events.is_empty());
+        assert_eq!(life_bar.serialize(), "");
+    }
+    #[test]
     fn serialize_lifebar() {
         let mut life_bar = LifeBar::new();
         life_bar.base_time = 256;
@@ -178,4 +297,73 @@ mod tests {
 
         assert_eq!(serialized_lifebar, "256|1,2657|1,10213|1,");
     }
+    #[test]
+    fn serialize_unmodified_lifebar() {
+        let original = "0|1,2657|0.50,10213|";
+        let mut life_bar = LifeBar::from_str(original).unwrap();
+        assert_eq!(life_bar.serialize(), original);
+
+        life_bar.clamp_values();
+        assert_eq!(life_bar.serialize(), original);
+
+        life_bar.push_event(LifeBarEvent { u: 12000, v: 0.25 });
+        assert_eq!(life_bar.serialize(), "|1,2657|0.5,10213|0.25,12000|1,");
+
+        let mut life_bar = LifeBar::from_str(original).unwrap();
+        life_bar.base_time = 256;
+        assert_eq!(life_bar.serialize(), "256|1,2657|0.5,10213|1,");
+    }
+    #[test]
+    fn lifebar_sorted() {
+        let mut life_bar = LifeBar::from_str("|1,1000|0.5,2000|0.4,2000|").unwrap();
+        assert!(life_bar.is_time_sorted());
+
+        life_bar.sort_by_time();
+        assert_eq!(life_bar.serialize(), "|1,1000|0.5,2000|0.4,2000|");
+    }
+    #[test]
+    fn lifebar_sort_by_time() {
+        let mut life_bar = LifeBar::from_str("|1,1000|0.25,3000|0.5,2000|").unwrap();
+        assert!(!life_bar.is_time_sorted());
+
+        life_bar.sort_by_time();
+
+        assert!(life_bar.is_time_sorted());
+        let times: Vec<u32> = life_bar.events.iter().map(|event| event.u).collect();
+        assert_eq!(times, vec![1000, 2000, 3000]);
+        assert_eq!(life_bar.life_at(2500), Some(0.375));
+    }
+    #[test]
+    fn lifebar_life_at() {
+        let life_bar = LifeBar::from_str("|1,1000|0.5,2000|").unwrap();
+
+        assert_eq!(life_bar.life_at(0), Some(1.0));
+        assert_eq!(life_bar.life_at(1500), Some(0.75));
+        assert_eq!(life_bar.life_at(3000), Some(0.5));
+        assert_eq!(LifeBar::new().life_at(0), None);
+    }
+    #[test]
+    fn lifebar_sampled() {
+        let life_bar = LifeBar::from_str("|1,1000|0.5,2000|0,2500|").unwrap();
+
+        let sampled: Vec<(u32, f32)> = life_bar.sampled(500).collect();
+
+        assert_eq!(sampled, life_bar.resample(500));
+        assert_eq!(
+            sampled,
+            vec![(1000, 1.0), (1500, 0.75), (2000, 0.5), (2500, 0.0)]
+        );
+        assert!(LifeBar::new().resample(500).is_empty());
+    }
+    #[test]
+    fn clamp_lifebar_values() {
+        let mut life_bar = LifeBar::from_str("256|1.0001,2657|-0.002,10213|0.5,12000|1,").unwrap();
+        assert_eq!(life_bar.events[0].v, 1.0001);
+
+        life_bar.clamp_values();
+
+        assert_eq!(life_bar.events[0].v, 1.0);
+        assert_eq!(life_bar.events[1].v, 0.0);
+        assert_eq!(life_bar.events[2].v, 0.5);
+    }
 }
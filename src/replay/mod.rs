@@ -1,24 +1,45 @@
 use crate::error::Error;
 use crate::types::*;
+use alloc::format;
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
 use life_bar::*;
 use replay_data::*;
-use std::borrow::Borrow;
-use std::fs::{self, File};
-use std::io::{BufReader, Cursor, Read};
-use std::path::Path;
-use std::str::FromStr;
-
-use crate::utils::file::ensure_replay_file;
-use crate::utils::lzma::decompress_replay_data;
-use crate::utils::read::*;
-use crate::utils::*;
+
 use chrono::NaiveDateTime;
 
+#[cfg(feature = "std")]
+use {
+    crate::utils::file::ensure_replay_file,
+    crate::utils::lzma::{
+        decompress_lzma_exact, decompress_lzma_stream, decompress_replay_data, replay_data_decoder,
+    },
+    crate::utils::read::*,
+    crate::utils::write::*,
+    crate::utils::*,
+    chrono::Utc,
+    options::{ParseOptions, ParseWarning},
+    std::borrow::Borrow,
+    std::fs::{self, File},
+    std::io::{self, BufRead, BufReader, Cursor, Read},
+    std::iter,
+    std::path::Path,
+    std::str::FromStr,
+};
+
+#[cfg(feature = "std")]
+pub mod analysis;
+pub mod api_score;
+pub mod header;
 pub mod life_bar;
+pub mod options;
 pub mod replay_data;
+pub mod summary;
+pub mod svg;
 
 /// Structure of a replay containing parsed values
-#[derive(Debug, Default)]
+#[derive(Debug, Default, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Replay {
     /// Game mode of the replay (0 = osu! Standard, 1 = Taiko, 2 = Catch the Beat, 3 = osu!mania)
     pub gamemode: Gamemode,
@@ -60,87 +81,654 @@ pub struct Replay {
     /// Parsed date and time of the play from the ticks timestamp
     pub play_date: NaiveDateTime,
 
+    /// Length of the compressed replay data read from the replay file, or set by `recompress`
+    /// (0 for a new replay). It's always computed again when writing the replay.
+    pub compressed_length: Integer,
     /// Uncompressed and parsed replay data
     pub replay_data: ReplayData,
     /// Online score ID
     pub score_id: Long,
-    // TODO: additionnal mod infos
+    /// Additional mod information, only present with the Target Practice mod
+    /// (total accuracy of all hits)
+    pub additional_mod_info: Option<Double>,
+    /// Unknown bytes found after the last known field,
+    /// preserved as-is to be written back (newer format or corrupted file)
+    pub trailing_bytes: Vec<u8>,
+    /// Problems recovered from while parsing the replay with lenient parse options,
+    /// including the ones of `ReplayData::warnings`
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub warnings: Vec<options::ParseWarning>,
+    /// Free notes about the replay kept in memory only (ex. the tool which processed it),
+    /// never written in the replay file
+    pub notes: Option<String>,
+}
+
+/// Stages reached while reading a replay, reported by `Replay::open_with_progress`
+#[cfg(feature = "std")]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ParseStage {
+    /// The whole file has been read
+    FileRead,
+    /// The decompression of the replay data is starting
+    DecompressStart,
+    /// The replay data has been decompressed
+    DecompressDone,
+    /// The replay is fully parsed
+    ParseDone,
 }
 
 impl Replay {
+    /// First game version writing the RNG seed in the replay data
+    pub const SEED_MIN_VERSION: Integer = 20130319;
+    /// First game version writing the online score ID
+    pub const SCORE_ID_MIN_VERSION: Integer = 20121008;
+    /// First game version writing the online score ID as a long instead of an integer
+    pub const LONG_SCORE_ID_MIN_VERSION: Integer = 20140721;
+    /// Game versions whose every value is parsed (seed, score ID as a long and additional mod
+    /// info). Replays of osu!lazer (from version 30000000) have extra data kept in `trailing_bytes`.
+    pub const SUPPORTED_VERSION_RANGE: core::ops::RangeInclusive<Integer> =
+        Self::LONG_SCORE_ID_MIN_VERSION..=29999999;
+    /// Minimum gameplay frames per second for `plausible_frame_density`
+    pub const MIN_FRAME_DENSITY: f64 = 10.0;
+    /// Minimum gameplay frames per second in osu!mania for `plausible_frame_density`
+    pub const MIN_MANIA_FRAME_DENSITY: f64 = 0.5;
+    /// Maximum gameplay frames per second for `plausible_frame_density`
+    pub const MAX_FRAME_DENSITY: f64 = 1000.0;
+    /// Maximum ScoreV1 total score, osu! storing it as a signed 32-bit integer
+    pub const MAX_SCORE_V1: Integer = i32::MAX as Integer;
+    /// Maximum ScoreV1 total score in osu!mania, where it's scaled to 1,000,000
+    pub const MAX_MANIA_SCORE_V1: Integer = 1_000_000;
+    /// Maximum ScoreV2 total score: 1,000,000 scaled by the highest mod multiplier
+    /// (HD, HR, DT and FL give 1.06 × 1.10 × 1.20 × 1.12, about 1.57)
+    pub const MAX_SCORE_V2: Integer = 1_600_000;
+
     pub fn new() -> Self {
         Self::default()
     }
 
-    pub fn open(path: &Path) -> Result<Self, Error> {
+    /// Whether the game version of the replay is in `SUPPORTED_VERSION_RANGE`,
+    /// otherwise some values may be missing or partially parsed
+    pub fn is_fully_supported(&self) -> bool {
+        Self::SUPPORTED_VERSION_RANGE.contains(&self.game_version)
+    }
+
+    /// Accuracy of the play from 0 - 1, computed from the hit counts according to the gamemode
+    pub fn accuracy(&self) -> Double {
+        let n300 = self.number_300s as Double;
+        let n100 = self.number_100s as Double;
+        let n50 = self.number_50s as Double;
+        let gekis = self.number_gekis as Double;
+        let katus = self.number_katus as Double;
+        let misses = self.number_misses as Double;
+
+        let (points, total) = match self.gamemode {
+            Gamemode::STD => (
+                300.0 * n300 + 100.0 * n100 + 50.0 * n50,
+                300.0 * (n300 + n100 + n50 + misses),
+            ),
+            Gamemode::TAIKO => (n300 + 0.5 * n100, n300 + n100 + misses),
+            Gamemode::CTB => (n300 + n100 + n50, n300 + n100 + n50 + katus + misses),
+            Gamemode::MANIA => (
+                300.0 * (n300 + gekis) + 200.0 * katus + 100.0 * n100 + 50.0 * n50,
+                300.0 * (n300 + gekis + katus + n100 + n50 + misses),
+            ),
+        };
+
+        if total == 0.0 {
+            return 0.0;
+        }
+        points / total
+    }
+
+    /// Hit counts of the replay mapped to gamemode-independent names
+    pub fn judgements(&self) -> Judgements {
+        let judgements = Judgements {
+            great: self.number_300s,
+            ok: self.number_100s,
+            miss: self.number_misses,
+            ..Judgements::default()
+        };
+
+        match self.gamemode {
+            Gamemode::STD => Judgements {
+                meh: self.number_50s,
+                ..judgements
+            },
+            Gamemode::CTB => Judgements {
+                meh: self.number_50s,
+                small_tick_miss: self.number_katus,
+                ..judgements
+            },
+            Gamemode::TAIKO => judgements,
+            Gamemode::MANIA => Judgements {
+                perfect: self.number_gekis,
+                good: self.number_katus,
+                meh: self.number_50s,
+                ..judgements
+            },
+        }
+    }
+
+    /// Heuristic (not official pp) to rank attempts of the same beatmap without a performance
+    /// calculator, between 0 and 1: `accuracy^4 * combo_ratio^0.8 * 0.97^misses`.
+    /// The combo ratio is 1 for a full combo, otherwise the greatest combo relative to
+    /// an estimate of the max combo (the hit object count, or the greatest combo if higher).
+    #[cfg(feature = "std")]
+    pub fn performance_proxy(&self) -> f64 {
+        let judgements = self.judgements();
+        let hit_objects = [
+            judgements.perfect,
+            judgements.great,
+            judgements.good,
+            judgements.ok,
+            judgements.meh,
+            judgements.miss,
+        ]
+        .iter()
+        .map(|count| *count as f64)
+        .sum::<f64>();
+
+        let estimated_max_combo = hit_objects.max(self.greatest_combo as f64);
+        let combo_ratio = if self.is_full_combo {
+            1.0
+        } else if estimated_max_combo == 0.0 {
+            0.0
+        } else {
+            self.greatest_combo as f64 / estimated_max_combo
+        };
+
+        self.accuracy().powi(4) * combo_ratio.powf(0.8) * 0.97f64.powi(self.number_misses as i32)
+    }
+
+    /// Estimate of the number of combo breaks not caused by misses (ex. slider breaks).
+    /// Since a play broken `n` times is made of `n + 1` combos, at least
+    /// `ceil(hits / greatest_combo) - 1` breaks happened, minus the misses. Slider ticks also
+    /// give combo, so this is a lower estimate. Always 0 for a full combo.
+    pub fn combo_breaks_estimate(&self) -> u32 {
+        if self.is_full_combo || self.greatest_combo == 0 {
+            return 0;
+        }
+
+        let judgements = self.judgements();
+        let hits = [
+            judgements.perfect,
+            judgements.great,
+            judgements.good,
+            judgements.ok,
+            judgements.meh,
+        ]
+        .iter()
+        .map(|count| *count as u32)
+        .sum::<u32>();
+
+        let breaks = hits.div_ceil(self.greatest_combo as u32).saturating_sub(1);
+        breaks.saturating_sub(self.number_misses as u32)
+    }
+
+    /// Total accuracy of all hits of a Target Practice play (the additional mod info),
+    /// `None` if the Target Practice mod isn't set
+    pub fn target_practice_accuracy(&self) -> Option<Double> {
+        if self.mods.contains(Mods::TARGET) {
+            self.additional_mod_info
+        } else {
+            None
+        }
+    }
+
+    /// URL of the score on the osu! website, `None` for offline plays (`score_id` of 0)
+    pub fn score_url(&self) -> Option<String> {
+        match self.score_id {
+            0 => None,
+            score_id => Some(format!("https://osu.ppy.sh/scores/{}", score_id)),
+        }
+    }
+
+    /// Relabel the hit counts from the layout of the replay gamemode to the layout of the `to`
+    /// gamemode, and set the gamemode, for example when reinterpreting a converted beatmap play.
+    /// Outside of osu!mania, gekis and katus are counted among the 300s and the 100s, while in
+    /// osu!mania they are separate MAX and 200 judgements: converting to osu!mania moves them out of
+    /// the 300s and 100s, converting from osu!mania moves them back.
+    /// Only the counts are relabeled, the score and the combo aren't recomputed.
+    pub fn convert_gamemode_counts(&mut self, to: Gamemode) {
+        match (&self.gamemode, &to) {
+            (Gamemode::MANIA, Gamemode::MANIA) => (),
+            (_, Gamemode::MANIA) => {
+                self.number_300s = self.number_300s.saturating_sub(self.number_gekis);
+                self.number_100s = self.number_100s.saturating_sub(self.number_katus);
+            }
+            (Gamemode::MANIA, _) => {
+                self.number_300s = self.number_300s.saturating_add(self.number_gekis);
+                self.number_100s = self.number_100s.saturating_add(self.number_katus);
+            }
+            _ => (),
+        }
+
+        self.gamemode = to;
+    }
+
+    /// Grade of the play, computed from the hit counts and the mods like osu! does
+    pub fn grade(&self) -> Grade {
+        let accuracy = self.accuracy();
+        let silver = self
+            .mods
+            .intersects(Mods::HIDDEN | Mods::FLASHLIGHT | Mods::FADE_IN);
+
+        let grade = match self.gamemode {
+            Gamemode::STD | Gamemode::TAIKO => {
+                let total =
+                    (self.number_300s + self.number_100s + self.number_50s + self.number_misses)
+                        as Double;
+                let ratio_300 = self.number_300s as Double / total;
+                let ratio_50 = self.number_50s as Double / total;
+                let no_miss = self.number_misses == 0;
+
+                if accuracy == 1.0 {
+                    Grade::SS
+                } else if ratio_300 > 0.9 && ratio_50 < 0.01 && no_miss {
+                    Grade::S
+                } else if (ratio_300 > 0.8 && no_miss) || ratio_300 > 0.9 {
+                    Grade::A
+                } else if (ratio_300 > 0.7 && no_miss) || ratio_300 > 0.8 {
+                    Grade::B
+                } else if ratio_300 > 0.6 {
+                    Grade::C
+                } else {
+                    Grade::D
+                }
+            }
+            Gamemode::CTB => {
+                if accuracy == 1.0 {
+                    Grade::SS
+                } else if accuracy > 0.98 {
+                    Grade::S
+                } else if accuracy > 0.94 {
+                    Grade::A
+                } else if accuracy > 0.9 {
+                    Grade::B
+                } else if accuracy > 0.85 {
+                    Grade::C
+                } else {
+                    Grade::D
+                }
+            }
+            Gamemode::MANIA => {
+                if accuracy == 1.0 {
+                    Grade::SS
+                } else if accuracy > 0.95 {
+                    Grade::S
+                } else if accuracy > 0.9 {
+                    Grade::A
+                } else if accuracy > 0.8 {
+                    Grade::B
+                } else if accuracy > 0.7 {
+                    Grade::C
+                } else {
+                    Grade::D
+                }
+            }
+        };
+
+        match grade {
+            Grade::SS if silver => Grade::SSH,
+            Grade::S if silver => Grade::SH,
+            grade => grade,
+        }
+    }
+
+    /// String hashed by osu! to compute the replay hash: the greatest combo, `osu`, the player
+    /// name, the beatmap hash, the total score and the grade name used by osu! (ex. `SH`)
+    pub fn hash_input(&self) -> String {
+        format!(
+            "{}osu{}{}{}{}",
+            self.greatest_combo,
+            self.player_name,
+            self.map_hash,
+            self.total_score,
+            self.grade().osu_name()
+        )
+    }
+
+    /// Compute the osu! replay MD5 hash from the current values of the replay (see `hash_input`)
+    pub fn compute_replay_hash(&self) -> String {
+        format!("{:x}", md5::compute(self.hash_input()))
+    }
+
+    /// Check that the stored replay hash matches the values of the replay
+    pub fn verify_replay_hash(&self) -> bool {
+        self.replay_hash == self.compute_replay_hash()
+    }
+
+    /// Make the replay internally consistent after modifying it, by recomputing the replay hash
+    /// and the compressed replay data length
+    #[cfg(feature = "std")]
+    pub fn finalize(&mut self) -> Result<(), Error> {
+        self.replay_hash = self.compute_replay_hash();
+        self.compressed_length = Self::compressed_length_field(self.replay_data.compressed_len()?)?;
+        Ok(())
+    }
+
+    /// Total score with comma thousands separators (ex. `13,392,443`)
+    pub fn formatted_score(&self) -> String {
+        let digits = self.total_score.to_string();
+        let mut formatted = String::with_capacity(digits.len() + digits.len() / 3);
+
+        for (i, digit) in digits.chars().enumerate() {
+            if i > 0 && (digits.len() - i).is_multiple_of(3) {
+                formatted.push(',');
+            }
+            formatted.push(digit);
+        }
+
+        formatted
+    }
+
+    /// Stable key identifying a play, to detect duplicates of the same replay.
+    /// Composed of the beatmap hash, the player name and the unix timestamp of the play date
+    /// (`map_hash:player_name:timestamp`), unlike the replay hash it doesn't depend on
+    /// values which may change when the replay is edited.
+    pub fn dedup_key(&self) -> String {
+        format!(
+            "{}:{}:{}",
+            self.map_hash,
+            self.player_name,
+            self.play_date.and_utc().timestamp()
+        )
+    }
+
+    /// Compact JSON object of the play metadata (player name, mod acronyms, score, accuracy,
+    /// grade and UTC play date), without the bulky replay data, ex.
+    /// `{"player":"...","mods":["HD"],"score":13392443,"accuracy":0.99,"grade":"SH","date":"..."}`
+    pub fn metadata_json(&self) -> String {
+        let mods: Vec<String> = self
+            .mods
+            .acronyms()
+            .iter()
+            .map(|acronym| json_string(acronym))
+            .collect();
+
+        format!(
+            "{{\"player\":{},\"mods\":[{}],\"score\":{},\"accuracy\":{},\"grade\":\"{:?}\",\"date\":\"{}\"}}",
+            json_string(&self.player_name),
+            mods.join(","),
+            self.total_score,
+            self.accuracy(),
+            self.grade(),
+            self.play_date.format("%Y-%m-%dT%H:%M:%SZ")
+        )
+    }
+
+    /// Absolute start and end times (in milliseconds) of the gameplay frames,
+    /// skipping the leading convention frames and the seed frame.
+    /// Useful to align the replay with the beatmap audio. Returns `(0, 0)` without frames.
+    pub fn play_window(&self) -> (Long, Long) {
+        self.replay_data.play_window()
+    }
+
+    /// Sanity check of the total score against the maximum reachable score, to flag corrupted
+    /// values: `MAX_SCORE_V2` with the ScoreV2 mod, otherwise `MAX_MANIA_SCORE_V1` in osu!mania
+    /// and `MAX_SCORE_V1` in the other gamemodes
+    pub fn score_plausible(&self) -> bool {
+        let max_score = if self.mods.contains(Mods::SCORE_V2) {
+            Self::MAX_SCORE_V2
+        } else if self.gamemode == Gamemode::MANIA {
+            Self::MAX_MANIA_SCORE_V1
+        } else {
+            Self::MAX_SCORE_V1
+        };
+
+        self.total_score <= max_score
+    }
+
+    /// Sanity check of the number of gameplay frames per second (of real time, the speed mods
+    /// being taken into account) for import filtering: fewer frames suggest a stub replay,
+    /// many more a corrupted one. osu! records around 60 frames per second, except in osu!mania
+    /// where frames are only recorded on key changes. The density must be in
+    /// `MIN_FRAME_DENSITY..=MAX_FRAME_DENSITY`, or `MIN_MANIA_FRAME_DENSITY..=MAX_FRAME_DENSITY`
+    /// in osu!mania. Always false without at least 2 gameplay frames at different times.
+    pub fn plausible_frame_density(&self) -> bool {
+        let (start, end) = self.play_window();
+        let frame_count = self.replay_data.gameplay_frames().len();
+        if end <= start || frame_count < 2 {
+            return false;
+        }
+
+        let real_duration_s = (end - start) as f64 / 1000.0 / self.mods.speed_multiplier();
+        let density = (frame_count - 1) as f64 / real_duration_s;
+        let min_density = match self.gamemode {
+            Gamemode::MANIA => Self::MIN_MANIA_FRAME_DENSITY,
+            _ => Self::MIN_FRAME_DENSITY,
+        };
+
+        (min_density..=Self::MAX_FRAME_DENSITY).contains(&density)
+    }
+
+    /// Heuristic telling if the replay is a failed play:
+    /// the life bar ends empty (last value at 0) while the NoFail mod isn't enabled.
+    /// Always false for replays without life bar graph.
+    pub fn is_failed_play(&self) -> bool {
+        match self.life_bar_graph.events().last() {
+            Some(event) => event.v <= 0.0 && !self.mods.contains(Mods::NO_FAIL),
+            None => false,
+        }
+    }
+
+    /// Life bar graph of the replay
+    pub fn life_bar(&self) -> &LifeBar {
+        &self.life_bar_graph
+    }
+
+    /// Events of the life bar graph (empty without life bar), for callers only needing the
+    /// curve points. The life bar is parsed with the replay, so this can't fail.
+    pub fn life_bar_events(&self) -> Vec<LifeBarEvent> {
+        self.life_bar_graph.events().clone()
+    }
+
+    /// Replace the life bar graph of the replay, written in the osu! format
+    /// (see `LifeBar::serialize`) when the replay is written
+    pub fn set_life_bar(&mut self, life_bar: &LifeBar) {
+        self.life_bar_graph = life_bar.clone();
+    }
+
+    /// Remove the life bar graph of the replay to reduce its size.
+    /// The life bar is then written as an absent string.
+    pub fn strip_life_bar(&mut self) {
+        self.life_bar_graph = LifeBar::absent()
+    }
+
+    /// Check that the full combo flag doesn't contradict the miss count.
+    /// A full combo requires no misses, so a replay flagged as full combo with misses is corrupted.
+    pub fn full_combo_consistent(&self) -> bool {
+        Self::full_combo_consistent_with(self.is_full_combo, self.number_misses)
+    }
+
+    fn full_combo_consistent_with(is_full_combo: bool, number_misses: Short) -> bool {
+        !is_full_combo || number_misses == 0
+    }
+
+    /// Set every hit count at once, the counts being kept unchanged if the new ones are
+    /// inconsistent with the replay (see `full_combo_consistent`)
+    pub fn set_hit_counts(
+        &mut self,
+        number_300s: Short,
+        number_100s: Short,
+        number_50s: Short,
+        number_gekis: Short,
+        number_katus: Short,
+        number_misses: Short,
+    ) -> Result<(), Error> {
+        if !Self::full_combo_consistent_with(self.is_full_combo, number_misses) {
+            return Err(Error::InconsistentHitCounts);
+        }
+
+        self.number_300s = number_300s;
+        self.number_100s = number_100s;
+        self.number_50s = number_50s;
+        self.number_gekis = number_gekis;
+        self.number_katus = number_katus;
+        self.number_misses = number_misses;
+
+        Ok(())
+    }
+}
+
+/// Quote and escape a string as a JSON string
+fn json_string(s: &str) -> String {
+    let mut escaped = String::with_capacity(s.len() + 2);
+    escaped.push('"');
+    for c in s.chars() {
+        match c {
+            '"' => escaped.push_str("\\\""),
+            '\\' => escaped.push_str("\\\\"),
+            '\n' => escaped.push_str("\\n"),
+            '\r' => escaped.push_str("\\r"),
+            '\t' => escaped.push_str("\\t"),
+            c if (c as u32) < 0x20 => escaped.push_str(&format!("\\u{:04x}", c as u32)),
+            c => escaped.push(c),
+        }
+    }
+    escaped.push('"');
+    escaped
+}
+
+#[cfg(feature = "std")]
+impl Replay {
+    /// Same as `new`, with the play date set to the current time instead of the unix epoch
+    pub fn with_current_time() -> Self {
+        Self {
+            play_date: Utc::now().naive_utc(),
+            ..Self::default()
+        }
+    }
+
+    pub fn open<P: AsRef<Path>>(path: P) -> Result<Self, Error> {
+        let path = path.as_ref();
         ensure_replay_file(path)?;
 
         let file = File::open(path).map_err(|_| Error::CantOpenFile)?;
         file.borrow().try_into()
     }
 
-    pub fn write(self, path: &Path) -> Result<(), Box<dyn std::error::Error>> {
+    /// Same as `open`, calling `progress` each time a parsing stage is reached
+    pub fn open_with_progress<P: AsRef<Path>, F: FnMut(ParseStage)>(
+        path: P,
+        mut progress: F,
+    ) -> Result<Self, Error> {
+        let path = path.as_ref();
         ensure_replay_file(path)?;
 
-        let buffer: Vec<u8> = self.try_into()?;
-        Ok(fs::write(path, buffer)?)
+        let bytes = fs::read(path).map_err(|_| Error::FileBufferingError)?;
+        progress(ParseStage::FileRead);
+
+        let (mut replay, consumed) = Self::parse(&bytes, &ParseOptions::default(), &mut progress)?;
+        replay.trailing_bytes = bytes[consumed..].to_vec();
+
+        Ok(replay)
     }
 
-    fn read_play_date<R: Read>(buf: &mut R) -> ReadResult<NaiveDateTime> {
-        let timestamp_ticks = read_long(buf)?;
-        Ok(ticks_to_datetime(timestamp_ticks))
+    /// Same as `open`, parsing the replay with the given options
+    pub fn open_with_options<P: AsRef<Path>>(
+        path: P,
+        options: &ParseOptions,
+    ) -> Result<Self, Error> {
+        let path = path.as_ref();
+        ensure_replay_file(path)?;
+
+        let bytes = fs::read(path).map_err(|_| Error::FileBufferingError)?;
+        Self::from_bytes_with_options(&bytes, options)
     }
-}
 
-impl TryFrom<Replay> for Vec<u8> {
-    type Error = Error;
+    /// Parse a replay from the raw bytes of a replay file.
+    /// Any bytes left after the last known field are kept in `trailing_bytes`.
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, Error> {
+        Self::from_bytes_with_options(bytes, &ParseOptions::default())
+    }
 
-    fn try_from(replay: Replay) -> Result<Self, Error> {
-        let mut buffer = Vec::<u8>::new();
+    /// Parse a base64-encoded replay file (standard alphabet with padding),
+    /// surrounding whitespace being ignored
+    #[cfg(feature = "base64")]
+    pub fn from_base64(s: &str) -> Result<Self, Error> {
+        use base64::Engine;
 
-        buffer.push(replay.gamemode.borrow().into());
-        buffer.append(&mut replay.game_version.to_le_bytes().to_vec());
-        write_string(&Some(&replay.map_hash), &mut buffer);
-        write_string(&Some(&replay.player_name), &mut buffer);
-        write_string(&Some(&replay.replay_hash), &mut buffer);
-        buffer.append(&mut replay.number_300s.to_le_bytes().to_vec());
-        buffer.append(&mut replay.number_100s.to_le_bytes().to_vec());
-        buffer.append(&mut replay.number_50s.to_le_bytes().to_vec());
-        buffer.append(&mut replay.number_gekis.to_le_bytes().to_vec());
-        buffer.append(&mut replay.number_katus.to_le_bytes().to_vec());
-        buffer.append(&mut replay.number_misses.to_le_bytes().to_vec());
-        buffer.append(&mut replay.total_score.to_le_bytes().to_vec());
-        buffer.append(&mut replay.greatest_combo.to_le_bytes().to_vec());
-        buffer.push(replay.is_full_combo.into());
-        buffer.append(&mut replay.mods.bits().to_le_bytes().to_vec());
-        write_string(&Some(&replay.life_bar_graph.serialize()), &mut buffer);
-        buffer.append(&mut datetime_to_ticks(replay.play_date).to_le_bytes().to_vec());
-        let mut replay_data_compressed: Vec<u8> = replay.replay_data.borrow().try_into()?;
-        buffer.append(
-            &mut (replay_data_compressed.len() as Integer)
-                .to_le_bytes()
-                .to_vec(),
-        );
+        let bytes = base64::engine::general_purpose::STANDARD
+            .decode(s.trim())
+            .map_err(|_| Error::InvalidBase64)?;
+        Self::from_bytes(&bytes)
+    }
 
-        buffer.append(&mut replay_data_compressed);
-        buffer.append(&mut replay.score_id.to_le_bytes().to_vec());
+    /// Same as `from_bytes`, parsing the replay with the given options
+    pub fn from_bytes_with_options(bytes: &[u8], options: &ParseOptions) -> Result<Self, Error> {
+        let (mut replay, consumed) = Self::parse(bytes, options, &mut |_| ())?;
+        if options.reject_trailing_bytes && consumed < bytes.len() {
+            return Err(Error::UnexpectedTrailingBytes {
+                len: bytes.len() - consumed,
+            });
+        }
+        replay.trailing_bytes = bytes[consumed..].to_vec();
 
-        Ok(buffer)
+        Ok(replay)
     }
-}
 
-impl TryFrom<Vec<u8>> for Replay {
-    type Error = Error;
+    /// Parse a replay from the raw bytes of a replay file,
+    /// also returning the number of bytes consumed by the replay.
+    /// Useful when the replay is followed by other data in the same stream.
+    /// Unlike `from_bytes`, the bytes following the replay are not read.
+    pub fn parse_bytes(bytes: &[u8]) -> Result<(Self, usize), Error> {
+        Self::parse(bytes, &ParseOptions::default(), &mut |_| ())
+    }
 
-    fn try_from(value: Vec<u8>) -> Result<Self, Self::Error> {
-        let buffer = &mut Cursor::new(value);
+    /// Parse every replay of a buffer made of concatenated replay files, until the end of the buffer.
+    /// Parsing stops after the first replay failing to parse, as the start of the next one is unknown.
+    pub fn parse_all(data: &[u8]) -> Vec<Result<Self, Error>> {
+        let mut replays = Vec::new();
+        let mut offset = 0;
+
+        while offset < data.len() {
+            match Self::parse_bytes(&data[offset..]) {
+                Ok((replay, consumed)) => {
+                    replays.push(Ok(replay));
+                    offset += consumed;
+                }
+                Err(e) => {
+                    replays.push(Err(e));
+                    break;
+                }
+            }
+        }
 
-        let gamemode: Gamemode = Gamemode::try_from(read::read_byte(buffer)?)?;
+        replays
+    }
+
+    fn parse<F: FnMut(ParseStage)>(
+        bytes: &[u8],
+        options: &ParseOptions,
+        progress: &mut F,
+    ) -> Result<(Self, usize), Error> {
+        let buffer = &mut Cursor::new(bytes);
+
+        let mut warnings = Vec::new();
+        let gamemode_value = read::read_byte(buffer)?;
+        let gamemode = match Gamemode::try_from(gamemode_value) {
+            Err(Error::InvalidGamemode) if options.unknown_gamemode_as_std => {
+                warnings.push(ParseWarning::UnknownGamemode {
+                    value: gamemode_value,
+                });
+                Gamemode::STD
+            }
+            gamemode => gamemode?,
+        };
 
         let game_version = read::read_integer(buffer)?;
+        if options.check_game_version && !ParseOptions::is_plausible_game_version(game_version) {
+            return Err(Error::UnexpectedGameVersion {
+                version: game_version,
+            });
+        }
 
         let map_hash = read::read_string(buffer)?.unwrap_or_default();
-        let player_name = read::read_string(buffer)?.unwrap_or_default();
+        let player_name = match read::read_string_bytes(buffer)? {
+            Some(bytes) => options.player_name_encoding.decode(bytes)?,
+            None => String::new(),
+        };
         let replay_hash = read::read_string(buffer)?.unwrap_or_default();
 
         let number_300s = read::read_short(buffer)?;
@@ -159,24 +747,72 @@ impl TryFrom<Vec<u8>> for Replay {
             _ => return Err(Error::UnexpectedFullComboValue),
         };
 
-        let mods = read::read_integer(buffer)?.into();
-        let life_bar_graph = LifeBar::from_str(&read::read_string(buffer)?.unwrap_or_default())?;
+        let mods: Mods = read::read_integer(buffer)?.into();
+        let mut life_bar_graph = match read::read_string(buffer)? {
+            Some(life_bar_graph) => LifeBar::from_str(&life_bar_graph)?,
+            None => LifeBar::absent(),
+        };
+        if options.clamp_life_bar {
+            life_bar_graph.clamp_values();
+        }
         let play_date = Self::read_play_date(buffer)?;
         let compressed_length = read::read_integer(buffer)?;
 
+        progress(ParseStage::DecompressStart);
+        let data_start = buffer.position() as usize;
         let mut compressed_replay_data = vec![0u8; compressed_length as usize];
         buffer
             .read(&mut compressed_replay_data)
             .map_err(|_| Error::ReadBufferingError)?;
+        let (decompressed_replay_data, compressed_length) = if options.lenient_compressed_length {
+            // the declared length is only ignored when the LZMA stream doesn't end exactly there
+            match decompress_lzma_exact(&compressed_replay_data) {
+                Ok(data) => (data, compressed_length),
+                Err(_) => match decompress_lzma_stream(&bytes[data_start.min(bytes.len())..]) {
+                    Ok((data, stream_length)) => {
+                        buffer.set_position((data_start + stream_length) as u64);
+                        (data, stream_length as Integer)
+                    }
+                    Err(_) => (
+                        decompress_replay_data(&compressed_replay_data)?,
+                        compressed_length,
+                    ),
+                },
+            }
+        } else {
+            (
+                decompress_replay_data(&compressed_replay_data)?,
+                compressed_length,
+            )
+        };
+        progress(ParseStage::DecompressDone);
 
-        let decompressed_replay_data = decompress_replay_data(&compressed_replay_data)?;
-
-        let replay_data =
-            ReplayData::from_str(&String::from_utf8(decompressed_replay_data).unwrap_or_default())?;
+        let mut replay_data = ReplayData::parse_with_options(
+            &String::from_utf8(decompressed_replay_data).unwrap_or_default(),
+            options,
+        )?;
+        warnings.extend(replay_data.warnings.iter().cloned());
+        let data_end = (data_start + compressed_length as usize).min(bytes.len());
+        replay_data.set_compressed(bytes[data_start.min(data_end)..data_end].to_vec());
 
-        let score_id = read::read_long(buffer)?;
+        // replays of old versions, or truncated after the replay data, have no score ID
+        let at_end = buffer.position() as usize >= bytes.len();
+        let score_id = if at_end || game_version < Self::SCORE_ID_MIN_VERSION {
+            0
+        } else if game_version >= Self::LONG_SCORE_ID_MIN_VERSION {
+            read::read_long(buffer)?
+        } else {
+            read::read_integer(buffer)? as i32 as Long
+        };
+        // replays truncated after the score ID have no additional mod info
+        let additional_mod_info =
+            if mods.contains(Mods::TARGET) && (buffer.position() as usize) < bytes.len() {
+                Some(read::read_double(buffer)?)
+            } else {
+                None
+            };
 
-        Ok(Self {
+        let replay = Self {
             gamemode,
             game_version,
             map_hash,
@@ -194,12 +830,248 @@ impl TryFrom<Vec<u8>> for Replay {
             mods,
             life_bar_graph,
             play_date,
+            compressed_length,
             replay_data,
             score_id,
-        })
+            additional_mod_info,
+            trailing_bytes: Vec::new(),
+            warnings,
+            notes: None,
+        };
+
+        progress(ParseStage::ParseDone);
+
+        Ok((replay, buffer.position() as usize))
+    }
+
+    pub fn write<P: AsRef<Path>>(self, path: P) -> Result<(), Box<dyn std::error::Error>> {
+        let path = path.as_ref();
+        ensure_replay_file(path)?;
+
+        let buffer: Vec<u8> = self.try_into()?;
+        Ok(fs::write(path, buffer)?)
+    }
+
+    /// Bytes of the replay file, exactly as `write` would write them
+    pub fn to_osr_bytes(&self) -> Result<Vec<u8>, Error> {
+        self.to_bytes_as_version(self.game_version)
+    }
+
+    /// Compress the replay data again (ex. after editing the frames), returning the previous
+    /// and the new compressed lengths to see the savings. `compressed_length` is updated,
+    /// and the new compressed data is the one written with the replay.
+    pub fn recompress(&mut self) -> Result<(usize, usize), Error> {
+        let compressed = self.replay_data.compress()?;
+        let (previous_length, new_length) = (self.compressed_length as usize, compressed.len());
+        self.compressed_length = Self::compressed_length_field(new_length)?;
+        self.replay_data.set_compressed(compressed);
+
+        Ok((previous_length, new_length))
+    }
+
+    /// Value of the 32-bit compressed length field for replay data of `length` bytes
+    fn compressed_length_field(length: usize) -> Result<Integer, Error> {
+        Integer::try_from(length).map_err(|_| Error::ReplayDataTooLarge { length })
+    }
+
+    /// Same as `write`, using the byte layout of the given game version, which is also written
+    /// as the replay game version:
+    /// - the seed is omitted before `SEED_MIN_VERSION`
+    /// - the score ID is written as an integer before `LONG_SCORE_ID_MIN_VERSION`,
+    ///   and omitted before `SCORE_ID_MIN_VERSION`
+    pub fn write_as_version<P: AsRef<Path>>(
+        &self,
+        path: P,
+        version: Integer,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        let path = path.as_ref();
+        ensure_replay_file(path)?;
+
+        let buffer = self.to_bytes_as_version(version)?;
+        Ok(fs::write(path, buffer)?)
+    }
+
+    fn to_bytes_as_version(&self, version: Integer) -> Result<Vec<u8>, Error> {
+        let mut buffer = Vec::<u8>::new();
+
+        write_byte(self.gamemode.borrow().into(), &mut buffer);
+        write_integer(version, &mut buffer);
+        write_string(&Some(&self.map_hash), &mut buffer);
+        write_string(&Some(&self.player_name), &mut buffer);
+        write_string(&Some(&self.replay_hash), &mut buffer);
+        write_short(self.number_300s, &mut buffer);
+        write_short(self.number_100s, &mut buffer);
+        write_short(self.number_50s, &mut buffer);
+        write_short(self.number_gekis, &mut buffer);
+        write_short(self.number_katus, &mut buffer);
+        write_short(self.number_misses, &mut buffer);
+        write_integer(self.total_score, &mut buffer);
+        write_short(self.greatest_combo, &mut buffer);
+        write_byte(self.is_full_combo.into(), &mut buffer);
+        write_integer(self.mods.bits(), &mut buffer);
+        if self.life_bar_graph.is_absent() {
+            write_string(&None, &mut buffer);
+        } else {
+            write_string(&Some(&self.life_bar_graph.serialize()), &mut buffer);
+        }
+        write_long(datetime_to_ticks(self.play_date), &mut buffer);
+        let mut replay_data_compressed: Vec<u8> = if version < Self::SEED_MIN_VERSION {
+            let mut replay_data = self.replay_data.clone();
+            replay_data.seed = None;
+            replay_data
+                .frames
+                .retain(|frame| frame.w != ReplayFrame::SEED_FRAME_TIME);
+            replay_data.borrow().try_into()?
+        } else {
+            self.replay_data.borrow().try_into()?
+        };
+        write_integer(
+            Self::compressed_length_field(replay_data_compressed.len())?,
+            &mut buffer,
+        );
+
+        buffer.append(&mut replay_data_compressed);
+        if version >= Self::LONG_SCORE_ID_MIN_VERSION {
+            write_long(self.score_id, &mut buffer);
+        } else if version >= Self::SCORE_ID_MIN_VERSION {
+            write_integer(self.score_id as Integer, &mut buffer);
+        }
+        if self.mods.contains(Mods::TARGET) {
+            write_double(self.additional_mod_info.unwrap_or_default(), &mut buffer);
+        }
+        buffer.extend_from_slice(&self.trailing_bytes);
+
+        Ok(buffer)
+    }
+
+    /// Read only the `(map_hash, player_name)` pair of a replay file,
+    /// without reading the rest of the file nor decompressing the replay data
+    pub fn quick_identity<P: AsRef<Path>>(path: P) -> Result<(String, String), Error> {
+        let path = path.as_ref();
+        ensure_replay_file(path)?;
+
+        let file = File::open(path).map_err(|_| Error::CantOpenFile)?;
+        let buffer = &mut BufReader::new(file);
+
+        Gamemode::try_from(read::read_byte(buffer)?)?;
+        read::read_integer(buffer)?;
+
+        let map_hash = read::read_string(buffer)?.unwrap_or_default();
+        let player_name = read::read_string(buffer)?.unwrap_or_default();
+
+        Ok((map_hash, player_name))
+    }
+
+    /// Iterate over the frames of a replay file, decompressing the replay data progressively
+    /// instead of holding every frame in memory. Unparsable frames are skipped like when
+    /// parsing the whole replay, and the iteration stops at the seed frame.
+    pub fn stream_frames<P: AsRef<Path>>(
+        path: P,
+    ) -> Result<impl Iterator<Item = Result<ReplayFrame, Error>>, Error> {
+        let path = path.as_ref();
+        ensure_replay_file(path)?;
+
+        let file = File::open(path).map_err(|_| Error::CantOpenFile)?;
+        let mut buffer = BufReader::new(file);
+        let compressed_length = Self::skip_to_replay_data(&mut buffer)?;
+
+        let decoder = replay_data_decoder(buffer.take(compressed_length as u64))?;
+        let mut frames = BufReader::new(decoder).split(b',');
+        let mut done = false;
+
+        Ok(iter::from_fn(move || loop {
+            if done {
+                return None;
+            }
+            let frame = match frames.next()? {
+                Ok(frame) => String::from_utf8(frame).unwrap_or_default(),
+                Err(_) => {
+                    done = true;
+                    return Some(Err(Error::ReplayDataDecompressError));
+                }
+            };
+            match RawFrame::classify(&frame) {
+                RawFrame::Seed { .. } => done = true,
+                RawFrame::Frame(frame) => {
+                    if let Ok(frame) = ReplayFrame::from_str(frame) {
+                        return Some(Ok(frame));
+                    }
+                }
+            }
+        }))
+    }
+
+    /// Size in bytes of the decompressed replay data of a replay file, to check it before
+    /// parsing the whole replay. The replay data is decompressed without being kept in memory.
+    pub fn decompressed_size<P: AsRef<Path>>(path: P) -> Result<usize, Error> {
+        let path = path.as_ref();
+        ensure_replay_file(path)?;
+
+        let file = File::open(path).map_err(|_| Error::CantOpenFile)?;
+        let mut buffer = BufReader::new(file);
+        let compressed_length = Self::skip_to_replay_data(&mut buffer)?;
+
+        let mut decoder = replay_data_decoder(buffer.take(compressed_length as u64))?;
+        let size = io::copy(&mut decoder, &mut io::sink())
+            .map_err(|_| Error::ReplayDataDecompressError)?;
+
+        Ok(size as usize)
+    }
+
+    /// Read the values preceding the compressed replay data, returning its compressed length
+    fn skip_to_replay_data<R: Read>(buf: &mut R) -> ReadResult<Integer> {
+        Gamemode::try_from(read::read_byte(buf)?)?;
+        read::read_integer(buf)?;
+        for _ in 0..3 {
+            read::read_string(buf)?;
+        }
+        for _ in 0..6 {
+            read::read_short(buf)?;
+        }
+        read::read_integer(buf)?;
+        read::read_short(buf)?;
+        read::read_byte(buf)?;
+        read::read_integer(buf)?;
+        read::read_string(buf)?;
+        read::read_long(buf)?;
+
+        read::read_integer(buf)
+    }
+
+    fn read_play_date<R: Read>(buf: &mut R) -> ReadResult<NaiveDateTime> {
+        let timestamp_ticks = read_long(buf)?;
+        Ok(ticks_to_datetime(timestamp_ticks))
+    }
+}
+
+#[cfg(feature = "std")]
+impl TryFrom<Replay> for Vec<u8> {
+    type Error = Error;
+
+    fn try_from(replay: Replay) -> Result<Self, Error> {
+        replay.to_bytes_as_version(replay.game_version)
+    }
+}
+
+#[cfg(feature = "std")]
+impl TryFrom<&Replay> for Vec<u8> {
+    type Error = Error;
+
+    fn try_from(replay: &Replay) -> Result<Self, Error> {
+        replay.to_osr_bytes()
+    }
+}
+
+#[cfg(feature = "std")]
+impl TryFrom<Vec<u8>> for Replay {
+    type Error = Error;
+
+    fn try_from(value: Vec<u8>) -> Result<Self, Self::Error> {
+        Self::from_bytes(&value)
     }
 }
 
+#[cfg(feature = "std")]
 impl TryFrom<&File> for Replay {
     type Error = Error;
 
@@ -217,20 +1089,30 @@ impl TryFrom<&File> for Replay {
 
 ////////////////////////////////////////////////////////////////////////////////////////////////////
 
-#[cfg(test)]
+#[cfg(all(test, feature = "std"))]
 mod tests {
+    use std::fs;
     use std::path::Path;
 
-    use super::{Gamemode, Mods, Replay};
+    use super::{
+        decompress_lzma_exact, decompress_replay_data, Error, FromStr, Gamemode, Grade, Integer,
+        Judgements, Keys, LifeBar, LifeBarEvent, Long, Mods, ParseOptions, ParseStage,
+        ParseWarning, Replay, ReplayData, ReplayFrame,
+    };
+    use crate::utils::sample::{sample_replay, TEST_REPLAY_FILE};
 
-    const TEST_REPLAY_FILE: &'static str = "./assets/examples/replay-test.osr";
-    const TEST_NEW_REPLAY_FILE: &'static str = "./assets/examples/replay-new.osr";
+    const TEST_NEW_REPLAY_FILE: &str = "./assets/examples/replay-new.osr";
+    const TEST_FINALIZED_REPLAY_FILE: &str = "./assets/examples/replay-finalized.osr";
+    const TEST_MODERN_REPLAY_FILE: &str = "./assets/examples/replay-modern.osr";
+    const TEST_OLD_REPLAY_FILE: &str = "./assets/examples/replay-old.osr";
+    const TEST_LIFE_BAR_REPLAY_FILE: &str = "./assets/examples/replay-life-bar.osr";
+    const TEST_OSR_BYTES_REPLAY_FILE: &str = "./assets/examples/replay-osr-bytes.osr";
 
     #[test]
     fn open_replay() {
         let replay_path = Path::new(TEST_REPLAY_FILE);
 
-        let replay = Replay::open(&replay_path).unwrap();
+        let replay = Replay::open(replay_path).unwrap();
 
         assert_eq!(replay.gamemode, Gamemode::STD);
         assert_eq!(replay.game_version, 20210520);
@@ -245,7 +1127,7 @@ mod tests {
         assert_eq!(replay.number_misses, 0);
         assert_eq!(replay.total_score, 13392443);
         assert_eq!(replay.greatest_combo, 852);
-        assert_eq!(replay.is_full_combo, true);
+        assert!(replay.is_full_combo);
         assert_eq!(replay.mods, Mods::HIDDEN);
         assert_eq!(replay.life_bar_graph.events().len(), 0);
         assert_eq!(
@@ -258,11 +1140,1064 @@ mod tests {
     }
 
     #[test]
-    fn write_replay() {
-        let replay_path = Path::new(TEST_REPLAY_FILE);
+    fn replay_with_current_time() {
+        let replay = Replay::with_current_time();
 
-        let replay = Replay::open(&replay_path).unwrap();
+        let age = chrono::Utc::now().naive_utc() - replay.play_date;
+        assert!(age >= chrono::Duration::zero());
+        assert!(age < chrono::Duration::minutes(1));
+    }
 
-        replay.write(Path::new(TEST_NEW_REPLAY_FILE)).unwrap();
+    #[test]
+    fn open_replay_from_str_path() {
+        let replay = Replay::open(TEST_REPLAY_FILE).unwrap();
+
+        assert_eq!(replay.player_name, "Sailor SnoW");
+        assert_eq!(replay.score_id, 3760034870);
+    }
+
+    #[test]
+    fn parse_bytes_consumed() {
+        let bytes = fs::read(TEST_REPLAY_FILE).unwrap();
+
+        let (replay, consumed) = Replay::parse_bytes(&bytes).unwrap();
+
+        assert_eq!(replay.player_name, "Sailor SnoW");
+        assert_eq!(consumed, bytes.len());
+    }
+
+    #[test]
+    fn parse_concatenated_replays() {
+        let bytes = fs::read(TEST_REPLAY_FILE).unwrap();
+        let mut data = bytes.clone();
+        data.extend_from_slice(&bytes);
+
+        let replays = Replay::parse_all(&data);
+
+        assert_eq!(replays.len(), 2);
+        for replay in replays {
+            assert_eq!(replay.unwrap().player_name, "Sailor SnoW");
+        }
+    }
+
+    #[test]
+    fn parse_concatenated_replays_error() {
+        let mut data = fs::read(TEST_REPLAY_FILE).unwrap();
+        data.push(0xFF);
+
+        let replays = Replay::parse_all(&data);
+
+        assert_eq!(replays.len(), 2);
+        assert!(replays[0].is_ok());
+        assert!(replays[1].is_err());
+    }
+
+    #[test]
+    fn lenient_compressed_length() {
+        let options = ParseOptions {
+            lenient_compressed_length: true,
+            ..ParseOptions::default()
+        };
+        let bytes = fs::read(TEST_REPLAY_FILE).unwrap();
+        // the compressed length follows the play date, 119 bytes into the sample replay
+        let compressed_length = u32::from_le_bytes(bytes[119..123].try_into().unwrap());
+
+        for wrong_length in [compressed_length - 5, compressed_length + 5] {
+            let mut bytes = bytes.clone();
+            bytes[119..123].copy_from_slice(&wrong_length.to_le_bytes());
+
+            let replay = Replay::from_bytes(&bytes).unwrap();
+            assert_ne!(replay.score_id, 3760034870);
+
+            let replay = Replay::from_bytes_with_options(&bytes, &options).unwrap();
+            assert_eq!(replay.score_id, 3760034870);
+            assert_eq!(replay.replay_data.seed, Some(19290764));
+            assert!(replay.trailing_bytes.is_empty());
+        }
+    }
+
+    #[test]
+    fn lenient_compressed_length_well_formed() {
+        let options = ParseOptions {
+            lenient_compressed_length: true,
+            ..ParseOptions::default()
+        };
+        let bytes = fs::read(TEST_REPLAY_FILE).unwrap();
+        let compressed_length = u32::from_le_bytes(bytes[119..123].try_into().unwrap());
+
+        // the declared length of a well-formed file is kept by the strict decoding
+        let compressed_replay_data = &bytes[123..123 + compressed_length as usize];
+        assert!(decompress_lzma_exact(compressed_replay_data).is_ok());
+
+        let strict = Replay::from_bytes(&bytes).unwrap();
+        let replay = Replay::from_bytes_with_options(&bytes, &options).unwrap();
+        assert_eq!(replay.compressed_length, compressed_length);
+        assert_eq!(replay.score_id, strict.score_id);
+        assert_eq!(
+            replay.replay_data.frames.len(),
+            strict.replay_data.frames.len()
+        );
+    }
+
+    #[test]
+    fn unknown_gamemode_as_std() {
+        let options = ParseOptions {
+            unknown_gamemode_as_std: true,
+            ..ParseOptions::default()
+        };
+        let mut bytes = fs::read(TEST_REPLAY_FILE).unwrap();
+        bytes[0] = 0x05;
+
+        assert!(matches!(
+            Replay::from_bytes(&bytes),
+            Err(Error::InvalidGamemode)
+        ));
+        let replay = Replay::from_bytes_with_options(&bytes, &options).unwrap();
+        assert_eq!(replay.gamemode, Gamemode::STD);
+        assert_eq!(replay.player_name, "Sailor SnoW");
+        assert_eq!(
+            replay.warnings,
+            [ParseWarning::UnknownGamemode { value: 0x05 }]
+        );
+
+        bytes[0] = 0x00;
+        assert!(Replay::from_bytes_with_options(&bytes, &options)
+            .unwrap()
+            .warnings
+            .is_empty());
+    }
+
+    #[test]
+    fn parse_missing_score_id() {
+        let mut bytes = fs::read(TEST_REPLAY_FILE).unwrap();
+        // remove the score ID long following the replay data
+        bytes.truncate(bytes.len() - 8);
+
+        let (replay, consumed) = Replay::parse_bytes(&bytes).unwrap();
+
+        assert_eq!(consumed, bytes.len());
+        assert_eq!(replay.score_id, 0);
+        assert_eq!(replay.replay_data.seed, Some(19290764));
+    }
+
+    #[test]
+    fn preserve_trailing_bytes() {
+        let mut bytes = fs::read(TEST_REPLAY_FILE).unwrap();
+        bytes.extend_from_slice(&[0xDE, 0xAD, 0xBE, 0xEF]);
+
+        let replay = Replay::from_bytes(&bytes).unwrap();
+        assert_eq!(replay.trailing_bytes, vec![0xDE, 0xAD, 0xBE, 0xEF]);
+
+        let written: Vec<u8> = replay.try_into().unwrap();
+        let replay = Replay::from_bytes(&written).unwrap();
+        assert_eq!(replay.trailing_bytes, vec![0xDE, 0xAD, 0xBE, 0xEF]);
+    }
+
+    #[test]
+    fn reject_trailing_bytes() {
+        let options = ParseOptions {
+            reject_trailing_bytes: true,
+            ..ParseOptions::default()
+        };
+        let mut bytes = fs::read(TEST_REPLAY_FILE).unwrap();
+        assert!(Replay::from_bytes_with_options(&bytes, &options).is_ok());
+
+        bytes.extend_from_slice(&[0xDE, 0xAD, 0xBE, 0xEF]);
+        assert!(Replay::from_bytes(&bytes).is_ok());
+        assert!(matches!(
+            Replay::from_bytes_with_options(&bytes, &options),
+            Err(Error::UnexpectedTrailingBytes { len: 4 })
+        ));
+    }
+
+    #[test]
+    fn check_game_version() {
+        let options = ParseOptions {
+            check_game_version: true,
+            ..ParseOptions::default()
+        };
+        let mut bytes = fs::read(TEST_REPLAY_FILE).unwrap();
+        assert!(Replay::from_bytes_with_options(&bytes, &options).is_ok());
+
+        bytes[1..5].copy_from_slice(&30000016u32.to_le_bytes());
+        let replay = Replay::from_bytes_with_options(&bytes, &options).unwrap();
+        assert_eq!(replay.game_version, 30000016);
+
+        bytes[1..5].copy_from_slice(&42u32.to_le_bytes());
+        assert!(Replay::from_bytes(&bytes).is_ok());
+        assert!(matches!(
+            Replay::from_bytes_with_options(&bytes, &options),
+            Err(Error::UnexpectedGameVersion { version: 42 })
+        ));
+    }
+
+    #[test]
+    fn verify_sample_replay_hash() {
+        let replay = sample_replay();
+
+        assert_eq!(replay.replay_hash, "caf14311cabb3a6b67697d96db5e7824");
+        assert!(replay.verify_replay_hash());
+
+        let bytes: Vec<u8> = (&replay).try_into().unwrap();
+        let written = Replay::from_bytes(&bytes).unwrap();
+        assert_eq!(written.replay_hash, replay.replay_hash);
+        assert!(written.verify_replay_hash());
+    }
+
+    #[test]
+    fn hash_input() {
+        let replay = sample_replay();
+
+        assert_eq!(
+            replay.hash_input(),
+            "852osuSailor SnoW400751ddba867c309b16487d546dcfdd13392443SH"
+        );
+        assert_eq!(replay.compute_replay_hash(), replay.replay_hash);
+        assert_eq!(
+            replay.compute_replay_hash(),
+            format!("{:x}", md5::compute(replay.hash_input()))
+        );
+    }
+
+    #[test]
+    fn formatted_score() {
+        let mut replay = Replay::open(Path::new(TEST_REPLAY_FILE)).unwrap();
+        assert_eq!(replay.formatted_score(), "13,392,443");
+
+        replay.total_score = 999;
+        assert_eq!(replay.formatted_score(), "999");
+        replay.total_score = 100000;
+        assert_eq!(replay.formatted_score(), "100,000");
+    }
+
+    #[test]
+    fn dedup_key() {
+        let replay = Replay::open(Path::new(TEST_REPLAY_FILE)).unwrap();
+        let mut other = replay.clone();
+        assert_eq!(replay.dedup_key(), other.dedup_key());
+
+        other.map_hash = String::from("d41d8cd98f00b204e9800998ecf8427e");
+        assert_ne!(replay.dedup_key(), other.dedup_key());
+    }
+
+    #[test]
+    fn metadata_json() {
+        let mut replay = sample_replay();
+
+        let json: serde_json::Value = serde_json::from_str(&replay.metadata_json()).unwrap();
+        assert_eq!(json["player"], "Sailor SnoW");
+        assert_eq!(json["mods"], serde_json::json!(["HD"]));
+        assert_eq!(json["score"], 13392443);
+        assert_eq!(json["grade"], "SH");
+        assert_eq!(json["date"], "2021-07-08T18:26:50Z");
+
+        replay.player_name = String::from("\"quoted\"\\name");
+        let json: serde_json::Value = serde_json::from_str(&replay.metadata_json()).unwrap();
+        assert_eq!(json["player"], "\"quoted\"\\name");
+    }
+
+    #[test]
+    fn play_window() {
+        let mut replay = sample_replay();
+
+        // the gameplay starts before the audio (negative time) during the lead-in
+        assert_eq!(replay.play_window(), (-652, 159904));
+
+        replay.replay_data.frames.clear();
+        assert_eq!(replay.play_window(), (0, 0));
+    }
+
+    #[test]
+    fn failed_play() {
+        let mut replay = Replay::new();
+        assert!(!replay.is_failed_play());
+
+        replay.life_bar_graph = LifeBar::parse("|1,2657|0.5,10213|0,12000|").unwrap();
+        assert!(replay.is_failed_play());
+
+        replay.mods = Mods::NO_FAIL;
+        assert!(!replay.is_failed_play());
+    }
+
+    #[test]
+    fn life_bar_events() {
+        let mut replay = Replay::new();
+        assert!(replay.life_bar_events().is_empty());
+
+        replay.life_bar_graph = LifeBar::from_str("256|1,2657|0.8,10213|").unwrap();
+        let events = replay.life_bar_events();
+
+        assert_eq!(events.len(), 2);
+        assert_eq!((events[0].u, events[0].v), (2657, 1.0));
+        assert_eq!((events[1].u, events[1].v), (10213, 0.8));
+    }
+
+    #[test]
+    fn set_life_bar() {
+        let mut replay = sample_replay();
+
+        let mut life_bar = replay.life_bar().clone();
+        life_bar.push_event(LifeBarEvent { u: 2249, v: 0.75 });
+        replay.set_life_bar(&life_bar);
+        replay.write(TEST_LIFE_BAR_REPLAY_FILE).unwrap();
+
+        let replay = Replay::open(TEST_LIFE_BAR_REPLAY_FILE).unwrap();
+        let events = replay.life_bar().events();
+        assert_eq!(events.len(), 1);
+        assert_eq!(events[0].u, 2249);
+        assert_eq!(events[0].v, 0.75);
+        assert!(replay.life_bar().serialize().ends_with("|1,"));
+    }
+
+    #[test]
+    fn unmodified_life_bar_preserved() {
+        let original = "0|1,2657|0.50,10213|0.8,12000|";
+        let mut replay = sample_replay();
+        replay.life_bar_graph = LifeBar::parse(original).unwrap();
+        let bytes: Vec<u8> = replay.try_into().unwrap();
+
+        let replay = Replay::from_bytes(&bytes).unwrap();
+        let written: Vec<u8> = replay.clone().try_into().unwrap();
+
+        assert_eq!(written, bytes);
+        assert_eq!(replay.life_bar().serialize(), original);
+        assert_eq!(&written[109..111], [0x0B, original.len() as u8]);
+        assert_eq!(&written[111..111 + original.len()], original.as_bytes());
+    }
+
+    #[test]
+    fn strip_life_bar() {
+        let mut replay = Replay::open(Path::new(TEST_REPLAY_FILE)).unwrap();
+        replay.life_bar_graph = LifeBar::parse("256|1,2657|0.5,10213|1,").unwrap();
+
+        replay.strip_life_bar();
+        let written: Vec<u8> = replay.try_into().unwrap();
+
+        // life bar string right after the header fields and the mods
+        assert_eq!(written[109], 0x00);
+        let replay = Replay::from_bytes(&written).unwrap();
+        assert!(replay.life_bar_graph.events().is_empty());
+        assert_eq!(replay.player_name, "Sailor SnoW");
+    }
+
+    #[test]
+    fn write_unmodified_replay_exactly() {
+        let original = fs::read(TEST_REPLAY_FILE).unwrap();
+
+        let mut replay = sample_replay();
+        assert_eq!(replay.to_osr_bytes().unwrap(), original);
+
+        // edited frames are compressed again
+        replay.replay_data.frames[100].x += 1.0;
+        let x = replay.replay_data.frames[100].x;
+        let written = Replay::from_bytes(&replay.to_osr_bytes().unwrap()).unwrap();
+        assert_eq!(written.replay_data.frames[100].x, x);
+    }
+
+    #[test]
+    fn absent_life_bar_kept_absent() {
+        let mut replay = sample_replay();
+        replay.strip_life_bar();
+        let written: Vec<u8> = replay.try_into().unwrap();
+
+        let replay = Replay::from_bytes(&written).unwrap();
+        assert!(replay.life_bar_graph.is_absent());
+        let rewritten: Vec<u8> = replay.try_into().unwrap();
+        assert_eq!(rewritten, written);
+    }
+
+    #[test]
+    fn empty_life_bar_kept_present() {
+        let mut replay = sample_replay();
+        replay.life_bar_graph = LifeBar::parse("").unwrap();
+        assert!(!replay.life_bar_graph.is_absent());
+
+        let written: Vec<u8> = replay.try_into().unwrap();
+
+        // an empty life bar is only written as absent once stripped
+        assert_eq!(written[109..111], [0x0B, 0x00]);
+    }
+
+    #[test]
+    fn open_with_progress() {
+        let mut stages = Vec::new();
+
+        let replay =
+            Replay::open_with_progress(Path::new(TEST_REPLAY_FILE), |stage| stages.push(stage))
+                .unwrap();
+
+        assert_eq!(replay.player_name, "Sailor SnoW");
+        assert_eq!(
+            stages,
+            vec![
+                ParseStage::FileRead,
+                ParseStage::DecompressStart,
+                ParseStage::DecompressDone,
+                ParseStage::ParseDone
+            ]
+        );
+    }
+
+    #[test]
+    fn movement_complexity() {
+        let replay = sample_replay();
+
+        let complexity = replay.replay_data.movement_complexity();
+        assert!(complexity.is_finite());
+        assert!(complexity > 0.0);
+    }
+
+    #[test]
+    fn round_trip_approx_eq() {
+        let replay = sample_replay();
+
+        let bytes: Vec<u8> = replay.clone().try_into().unwrap();
+        let round_tripped = Replay::from_bytes(&bytes).unwrap();
+
+        assert!(replay
+            .replay_data
+            .approx_eq(&round_tripped.replay_data, 0.001));
+    }
+
+    #[test]
+    fn recompress() {
+        let mut replay = sample_replay();
+        assert_eq!(replay.compressed_length, 50418);
+
+        let (previous_length, new_length) = replay.recompress().unwrap();
+
+        assert_eq!(previous_length, 50418);
+        assert_eq!(replay.compressed_length as usize, new_length);
+        assert!(new_length.abs_diff(previous_length) < previous_length / 10);
+        let bytes: Vec<u8> = replay.try_into().unwrap();
+        let replay = Replay::from_bytes(&bytes).unwrap();
+        assert_eq!(replay.compressed_length as usize, new_length);
+        assert_eq!(replay.replay_data.seed, Some(19290764));
+    }
+
+    #[test]
+    fn compress_within_budget() {
+        let replay = sample_replay();
+        let full: Vec<u8> = (&replay.replay_data).try_into().unwrap();
+
+        let compressed = replay
+            .replay_data
+            .to_compressed_within(full.len() / 3)
+            .unwrap();
+        assert!(compressed.len() <= full.len() / 3);
+
+        let decompressed = decompress_replay_data(&compressed).unwrap();
+        let replay_data = ReplayData::from_str(&String::from_utf8(decompressed).unwrap()).unwrap();
+        assert!(replay_data.frames.len() < replay.replay_data.frames.len());
+        assert_eq!(replay_data.seed, Some(19290764));
+        assert_eq!(replay_data.play_window(), replay.play_window());
+
+        assert!(matches!(
+            replay.replay_data.to_compressed_within(10),
+            Err(Error::CompressedSizeExceeded { max_bytes: 10 })
+        ));
+    }
+
+    #[test]
+    fn key_combinations_used() {
+        let replay = sample_replay();
+
+        let combinations = replay.replay_data.key_combinations_used(false);
+        assert!(combinations.contains(&(Keys::M1 | Keys::K1)));
+        assert!(combinations.contains(&(Keys::M2 | Keys::K2)));
+        assert!(!combinations.contains(&Keys::empty()));
+
+        let combinations = replay.replay_data.key_combinations_used(true);
+        assert!(combinations.contains(&Keys::empty()));
+    }
+
+    #[test]
+    fn centroid() {
+        let replay = sample_replay();
+
+        for (x, y) in [
+            replay.replay_data.centroid(),
+            replay.replay_data.time_weighted_centroid(),
+        ] {
+            assert!((0.0..=ReplayFrame::MAX_X).contains(&x));
+            assert!((0.0..=ReplayFrame::MAX_Y).contains(&y));
+        }
+    }
+
+    #[test]
+    fn stream_frames() {
+        let replay = Replay::open(Path::new(TEST_REPLAY_FILE)).unwrap();
+
+        let frames: Vec<ReplayFrame> = Replay::stream_frames(Path::new(TEST_REPLAY_FILE))
+            .unwrap()
+            .collect::<Result<_, _>>()
+            .unwrap();
+
+        assert_eq!(frames.len(), replay.replay_data.frames.len());
+        for (streamed, parsed) in frames.iter().zip(replay.replay_data.frames.iter()) {
+            assert_eq!(String::from(streamed), String::from(parsed));
+        }
+    }
+
+    #[test]
+    fn seed_frame_round_trip() {
+        let bytes = fs::read(TEST_REPLAY_FILE).unwrap();
+        let mut buffer = &bytes[..];
+        let compressed_length = Replay::skip_to_replay_data(&mut buffer).unwrap() as usize;
+        let original =
+            String::from_utf8(decompress_replay_data(&buffer[..compressed_length]).unwrap())
+                .unwrap();
+
+        let options = ParseOptions {
+            keep_seed_frame: true,
+            ..ParseOptions::default()
+        };
+        let replay = Replay::from_bytes_with_options(&bytes, &options).unwrap();
+
+        assert_eq!(String::from(&replay.replay_data), original);
+    }
+
+    #[test]
+    fn decompressed_size() {
+        let replay = sample_replay();
+
+        let size = Replay::decompressed_size(TEST_REPLAY_FILE).unwrap();
+
+        // each frame takes at least 8 bytes (`w|x|y|z,`)
+        assert!(size >= replay.replay_data.frames.len() * 8);
+        assert!(size < 1024 * 1024);
+    }
+
+    #[test]
+    fn quick_identity() {
+        let replay_path = Path::new(TEST_REPLAY_FILE);
+
+        let (map_hash, player_name) = Replay::quick_identity(replay_path).unwrap();
+
+        assert_eq!(map_hash, "400751ddba867c309b16487d546dcfdd");
+        assert_eq!(player_name, "Sailor SnoW");
+    }
+
+    #[test]
+    fn fully_supported_version() {
+        let mut replay = sample_replay();
+        assert!(replay.is_fully_supported());
+
+        replay.game_version = 20130101;
+        assert!(!replay.is_fully_supported());
+        replay.game_version = 30000001;
+        assert!(!replay.is_fully_supported());
+    }
+
+    #[test]
+    fn full_combo_consistent() {
+        let mut replay = Replay::new();
+        replay.is_full_combo = true;
+        assert!(replay.full_combo_consistent());
+
+        replay.is_full_combo = false;
+        replay.number_misses = 3;
+        assert!(replay.full_combo_consistent());
+    }
+
+    #[test]
+    fn full_combo_contradictory() {
+        let mut replay = Replay::new();
+        replay.is_full_combo = true;
+        replay.number_misses = 1;
+
+        assert!(!replay.full_combo_consistent());
+    }
+
+    #[test]
+    fn set_hit_counts() {
+        let mut replay = Replay::new();
+        replay.is_full_combo = true;
+
+        replay.set_hit_counts(500, 10, 2, 100, 5, 0).unwrap();
+        assert_eq!(replay.number_300s, 500);
+        assert_eq!(replay.number_katus, 5);
+
+        assert!(matches!(
+            replay.set_hit_counts(400, 10, 2, 100, 5, 3),
+            Err(Error::InconsistentHitCounts)
+        ));
+        assert_eq!(replay.number_300s, 500);
+        assert_eq!(replay.number_misses, 0);
+    }
+
+    #[test]
+    fn combo_breaks_estimate() {
+        let replay = sample_replay();
+        assert_eq!(replay.combo_breaks_estimate(), 0);
+
+        let mut replay = Replay::new();
+        replay.set_hit_counts(480, 20, 0, 0, 0, 1).unwrap();
+        replay.greatest_combo = 100;
+        assert_eq!(replay.combo_breaks_estimate(), 3);
+
+        replay.greatest_combo = 500;
+        assert_eq!(replay.combo_breaks_estimate(), 0);
+    }
+
+    #[test]
+    fn target_practice_accuracy() {
+        let mut replay = sample_replay();
+        assert_eq!(replay.target_practice_accuracy(), None);
+
+        replay.mods |= Mods::TARGET;
+        replay.additional_mod_info = Some(0.9525);
+        let bytes: Vec<u8> = replay.try_into().unwrap();
+
+        let replay = Replay::from_bytes(&bytes).unwrap();
+        assert_eq!(replay.target_practice_accuracy(), Some(0.9525));
+        assert!(replay.trailing_bytes.is_empty());
+    }
+
+    #[test]
+    fn parse_truncated_additional_mod_info() {
+        let mut replay = sample_replay();
+        replay.mods |= Mods::TARGET;
+        replay.additional_mod_info = Some(0.9525);
+        let mut bytes: Vec<u8> = replay.try_into().unwrap();
+
+        // cut off right after the score ID
+        bytes.truncate(bytes.len() - 8);
+        let replay = Replay::from_bytes(&bytes).unwrap();
+        assert_eq!(replay.score_id, 3760034870);
+        assert_eq!(replay.additional_mod_info, None);
+
+        // cut off in the middle of the additional mod info
+        bytes.extend_from_slice(&0.9525f64.to_le_bytes()[..4]);
+        assert!(matches!(
+            Replay::from_bytes(&bytes),
+            Err(Error::ReadBufferingError)
+        ));
+    }
+
+    #[test]
+    fn to_osr_bytes() {
+        let replay = sample_replay();
+
+        let bytes = replay.to_osr_bytes().unwrap();
+
+        let converted: Vec<u8> = (&replay).try_into().unwrap();
+        assert_eq!(bytes, converted);
+        replay.write(TEST_OSR_BYTES_REPLAY_FILE).unwrap();
+        assert_eq!(bytes, fs::read(TEST_OSR_BYTES_REPLAY_FILE).unwrap());
+    }
+
+    #[test]
+    fn player_name_with_separators_round_trip() {
+        let mut replay = sample_replay();
+        replay.player_name = "Sailor|SnoW, ローマ字".to_string();
+        let bytes: Vec<u8> = replay.clone().try_into().unwrap();
+
+        let written = Replay::from_bytes(&bytes).unwrap();
+
+        assert_eq!(written.player_name, replay.player_name);
+        assert_eq!(written.map_hash, replay.map_hash);
+        assert_eq!(
+            written.replay_data.frames.len(),
+            replay.replay_data.frames.len()
+        );
+        assert!(written.trailing_bytes.is_empty());
+    }
+
+    #[test]
+    fn replay_data_total_distance() {
+        let replay = sample_replay();
+
+        let total_distance = replay.replay_data.total_distance();
+
+        assert!(total_distance > 0.0);
+        assert!((total_distance - replay.analyze().cursor_distance).abs() < 1e-6);
+    }
+
+    #[cfg(target_pointer_width = "64")]
+    #[test]
+    fn compressed_length_field() {
+        assert_eq!(Replay::compressed_length_field(50418), Ok(50418));
+        assert_eq!(
+            Replay::compressed_length_field(u32::MAX as usize),
+            Ok(u32::MAX)
+        );
+
+        let length = u32::MAX as usize + 1;
+        assert_eq!(
+            Replay::compressed_length_field(length),
+            Err(Error::ReplayDataTooLarge { length })
+        );
+    }
+
+    #[test]
+    fn replay_data_compressed_len() {
+        let replay = sample_replay();
+
+        let compressed_len = replay.replay_data.compressed_len().unwrap();
+
+        assert!(compressed_len > 0);
+        let compressed: Vec<u8> = (&replay.replay_data).try_into().unwrap();
+        assert_eq!(compressed_len, compressed.len());
+    }
+
+    #[test]
+    fn score_plausible() {
+        let mut replay = sample_replay();
+        assert!(replay.score_plausible());
+
+        replay.total_score = u32::MAX - 1;
+        assert!(!replay.score_plausible());
+
+        replay.mods |= Mods::SCORE_V2;
+        replay.total_score = 1_000_000;
+        assert!(replay.score_plausible());
+        replay.total_score = 13392443;
+        assert!(!replay.score_plausible());
+
+        replay.mods = Mods::NONE;
+        replay.gamemode = Gamemode::MANIA;
+        replay.total_score = 1_000_000;
+        assert!(replay.score_plausible());
+        replay.total_score = 1_000_001;
+        assert!(!replay.score_plausible());
+    }
+
+    #[test]
+    fn plausible_frame_density() {
+        let replay_with_frames = |gamemode: Gamemode, mods: Mods, times: &[Long]| {
+            let samples: Vec<(Long, f32, f32, Integer)> =
+                times.iter().map(|&time| (time, 0.0, 0.0, 0)).collect();
+            Replay {
+                gamemode,
+                mods,
+                replay_data: ReplayData::from_samples(&samples),
+                ..Replay::default()
+            }
+        };
+        let every = |interval: Long, count: Long| -> Vec<Long> {
+            (0..count).map(|i| i * interval).collect()
+        };
+
+        assert!(
+            replay_with_frames(Gamemode::STD, Mods::NONE, &every(100, 11))
+                .plausible_frame_density()
+        );
+        assert!(
+            !replay_with_frames(Gamemode::STD, Mods::NONE, &every(101, 11))
+                .plausible_frame_density()
+        );
+        assert!(
+            replay_with_frames(Gamemode::STD, Mods::DOUBLETIME, &every(150, 11))
+                .plausible_frame_density()
+        );
+        assert!(
+            !replay_with_frames(Gamemode::STD, Mods::DOUBLETIME, &every(151, 11))
+                .plausible_frame_density()
+        );
+        assert!(
+            replay_with_frames(Gamemode::MANIA, Mods::NONE, &every(2000, 3))
+                .plausible_frame_density()
+        );
+        assert!(
+            !replay_with_frames(Gamemode::MANIA, Mods::NONE, &every(2001, 3))
+                .plausible_frame_density()
+        );
+
+        assert!(
+            replay_with_frames(Gamemode::TAIKO, Mods::NONE, &every(1, 1001))
+                .plausible_frame_density()
+        );
+        let mut times = every(1, 1001);
+        times.push(1000);
+        assert!(!replay_with_frames(Gamemode::TAIKO, Mods::NONE, &times).plausible_frame_density());
+
+        assert!(!replay_with_frames(Gamemode::STD, Mods::NONE, &[0]).plausible_frame_density());
+        assert!(Replay::open(TEST_REPLAY_FILE)
+            .unwrap()
+            .plausible_frame_density());
+    }
+
+    #[cfg(feature = "base64")]
+    #[test]
+    fn from_base64() {
+        use base64::Engine;
+
+        let bytes = fs::read(TEST_REPLAY_FILE).unwrap();
+        let encoded = base64::engine::general_purpose::STANDARD.encode(&bytes);
+
+        let replay = Replay::from_base64(&encoded).unwrap();
+
+        assert_eq!(replay.player_name, "Sailor SnoW");
+        assert_eq!(replay.score_id, 3760034870);
+        assert_eq!(
+            Replay::from_base64("not base64!").unwrap_err(),
+            Error::InvalidBase64
+        );
+    }
+
+    #[test]
+    fn notes_not_written() {
+        let mut replay = sample_replay();
+        let without_notes = replay.to_osr_bytes().unwrap();
+
+        replay.notes = Some("processed by osr-tool".to_string());
+        let cloned = replay.clone();
+        let bytes: Vec<u8> = replay.try_into().unwrap();
+
+        assert_eq!(cloned.notes.as_deref(), Some("processed by osr-tool"));
+        assert_eq!(bytes, without_notes);
+        assert!(!bytes.windows(9).any(|window| window == b"processed"));
+        assert_eq!(Replay::from_bytes(&bytes).unwrap().notes, None);
+    }
+
+    #[cfg(not(feature = "encoding"))]
+    #[test]
+    fn windows_1252_player_name_without_feature() {
+        use super::options::Encoding;
+
+        let mut replay = sample_replay();
+        replay.player_name = "Jos_".to_string();
+        let mut bytes = replay.to_osr_bytes().unwrap();
+        let name_start = bytes
+            .windows(4)
+            .position(|window| window == b"Jos_")
+            .unwrap();
+        bytes[name_start + 3] = 0xE9;
+
+        let options = ParseOptions {
+            player_name_encoding: Encoding::Windows1252,
+            ..ParseOptions::default()
+        };
+        assert!(Replay::open_with_options(TEST_REPLAY_FILE, &options).is_ok());
+        assert_eq!(
+            Replay::from_bytes_with_options(&bytes, &options).unwrap_err(),
+            Error::EncodingFeatureDisabled
+        );
+    }
+
+    #[cfg(feature = "encoding")]
+    #[test]
+    fn windows_1252_player_name() {
+        use super::options::Encoding;
+
+        let mut replay = sample_replay();
+        replay.player_name = "Jos_ M_ller".to_string();
+        let mut bytes = replay.to_osr_bytes().unwrap();
+        let name_start = bytes
+            .windows(11)
+            .position(|window| window == b"Jos_ M_ller")
+            .unwrap();
+        bytes[name_start + 3] = 0xE9;
+        bytes[name_start + 6] = 0xFC;
+
+        assert_eq!(
+            Replay::from_bytes(&bytes).unwrap_err(),
+            Error::CantReadString
+        );
+        let options = ParseOptions {
+            player_name_encoding: Encoding::Windows1252,
+            ..ParseOptions::default()
+        };
+        let replay = Replay::from_bytes_with_options(&bytes, &options).unwrap();
+        assert_eq!(replay.player_name, "José Müller");
+        assert_eq!(replay.map_hash, "400751ddba867c309b16487d546dcfdd");
+    }
+
+    #[test]
+    fn score_url() {
+        let mut replay = sample_replay();
+        assert_eq!(
+            replay.score_url().as_deref(),
+            Some("https://osu.ppy.sh/scores/3760034870")
+        );
+
+        replay.score_id = 0;
+        assert_eq!(replay.score_url(), None);
+    }
+
+    #[test]
+    fn convert_gamemode_counts() {
+        let mut replay = sample_replay();
+
+        replay.convert_gamemode_counts(Gamemode::MANIA);
+        assert_eq!(replay.gamemode, Gamemode::MANIA);
+        let judgements = replay.judgements();
+        assert_eq!(judgements.perfect, 140);
+        assert_eq!(judgements.great, 452);
+        assert_eq!(judgements.good, 2);
+        assert_eq!(judgements.ok, 0);
+        assert_eq!(judgements.meh, 0);
+        assert_eq!(judgements.miss, 0);
+
+        replay.convert_gamemode_counts(Gamemode::STD);
+        assert_eq!(replay.gamemode, Gamemode::STD);
+        assert_eq!(replay.number_300s, 592);
+        assert_eq!(replay.number_100s, 2);
+        assert_eq!(replay.number_gekis, 140);
+        assert_eq!(replay.number_katus, 2);
+    }
+
+    #[test]
+    fn performance_proxy() {
+        let mut ss = Replay::new();
+        ss.number_300s = 500;
+        ss.greatest_combo = 700;
+        ss.is_full_combo = true;
+
+        let mut missed = ss.clone();
+        missed.is_full_combo = false;
+        missed.set_hit_counts(480, 15, 2, 0, 0, 3).unwrap();
+        missed.greatest_combo = 300;
+
+        assert_eq!(ss.performance_proxy(), 1.0);
+        assert!(missed.performance_proxy() > 0.0);
+        assert!(ss.performance_proxy() > missed.performance_proxy());
+    }
+
+    #[test]
+    fn accuracy_and_grade() {
+        let replay = Replay::open(Path::new(TEST_REPLAY_FILE)).unwrap();
+
+        assert!((replay.accuracy() - 0.99776).abs() < 0.00001);
+        assert_eq!(replay.grade(), Grade::SH);
+    }
+
+    #[test]
+    fn std_judgements() {
+        let replay = Replay::open(Path::new(TEST_REPLAY_FILE)).unwrap();
+
+        let judgements = replay.judgements();
+
+        assert_eq!(
+            judgements,
+            Judgements {
+                perfect: 0,
+                great: 592,
+                good: 0,
+                ok: 2,
+                meh: 0,
+                miss: 0,
+                small_tick_miss: 0
+            }
+        );
+    }
+
+    #[test]
+    fn mania_judgements() {
+        let mut replay = Replay::new();
+        replay.gamemode = Gamemode::MANIA;
+        replay.number_gekis = 500;
+        replay.number_300s = 200;
+        replay.number_katus = 30;
+        replay.number_100s = 10;
+        replay.number_50s = 5;
+        replay.number_misses = 1;
+
+        let judgements = replay.judgements();
+
+        assert_eq!(
+            judgements,
+            Judgements {
+                perfect: 500,
+                great: 200,
+                good: 30,
+                ok: 10,
+                meh: 5,
+                miss: 1,
+                small_tick_miss: 0
+            }
+        );
+    }
+
+    #[test]
+    fn ctb_judgements() {
+        let mut replay = Replay::new();
+        replay.gamemode = Gamemode::CTB;
+        replay.number_300s = 400;
+        replay.number_100s = 20;
+        replay.number_50s = 150;
+        replay.number_katus = 6;
+        replay.number_misses = 2;
+
+        let judgements = replay.judgements();
+
+        assert_eq!(
+            judgements,
+            Judgements {
+                perfect: 0,
+                great: 400,
+                good: 0,
+                ok: 20,
+                meh: 150,
+                miss: 2,
+                small_tick_miss: 6
+            }
+        );
+    }
+
+    #[test]
+    fn finalize_after_edit() {
+        let mut replay = Replay::open(Path::new(TEST_REPLAY_FILE)).unwrap();
+        replay.replay_data.frames[10].x = 100.0;
+        replay.number_misses = 1;
+        replay.is_full_combo = false;
+        assert!(!replay.verify_replay_hash());
+
+        replay.finalize().unwrap();
+        assert_eq!(
+            replay.compressed_length as usize,
+            replay.replay_data.compressed_len().unwrap()
+        );
+        replay.write(Path::new(TEST_FINALIZED_REPLAY_FILE)).unwrap();
+
+        let replay = Replay::open(Path::new(TEST_FINALIZED_REPLAY_FILE)).unwrap();
+        assert_eq!(replay.replay_data.frames[10].x, 100.0);
+        assert!(replay.verify_replay_hash());
+    }
+
+    #[test]
+    fn write_replay() {
+        let replay_path = Path::new(TEST_REPLAY_FILE);
+
+        let replay = Replay::open(replay_path).unwrap();
+
+        replay.write(Path::new(TEST_NEW_REPLAY_FILE)).unwrap();
+    }
+
+    #[test]
+    fn write_as_modern_version() {
+        let replay = sample_replay();
+
+        replay
+            .write_as_version(TEST_MODERN_REPLAY_FILE, 20210520)
+            .unwrap();
+
+        let written = Replay::open(TEST_MODERN_REPLAY_FILE).unwrap();
+        assert_eq!(written.game_version, 20210520);
+        assert_eq!(written.replay_data.seed, Some(19290764));
+        assert_eq!(written.score_id, 3760034870);
+    }
+
+    #[test]
+    fn write_as_old_version() {
+        let replay = sample_replay();
+        let modern_len = fs::read(TEST_REPLAY_FILE).unwrap().len();
+
+        replay
+            .write_as_version(TEST_OLD_REPLAY_FILE, 20120101)
+            .unwrap();
+
+        let bytes = fs::read(TEST_OLD_REPLAY_FILE).unwrap();
+        let (written, consumed) = Replay::parse_bytes(&bytes).unwrap();
+        assert_eq!(consumed, bytes.len());
+        assert!(bytes.len() < modern_len);
+        assert_eq!(written.game_version, 20120101);
+        assert_eq!(written.replay_data.seed, None);
+        assert_eq!(written.score_id, 0);
+        assert_eq!(
+            written.replay_data.frames.len(),
+            replay.replay_data.frames.len()
+        );
     }
 }
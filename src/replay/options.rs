@@ -0,0 +1,95 @@
+use crate::error::Error;
+use crate::types::{Byte, Integer};
+use alloc::string::String;
+use alloc::vec::Vec;
+
+/// Options changing how a replay is parsed
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ParseOptions {
+    /// Accept frames with more than 4 `|`-separated values, ignoring the extra values
+    /// (written by some third-party replay generators)
+    pub lenient_frames: bool,
+    /// Keep the `-12345|0|0|seed` frame at the end of the frames, in addition to extracting the
+    /// seed into `ReplayData::seed`, to keep the exact frames of the file (a malformed seed
+    /// frame is kept too and written back as is)
+    pub keep_seed_frame: bool,
+    /// Reject replays whose game version isn't a plausible osu! version (a `yyyymmdd` date
+    /// between 2007 and 2100, or an osu!lazer version from 30000000), to detect files which
+    /// aren't replays since they have no magic
+    pub check_game_version: bool,
+    /// Clamp the life bar values into `0.0..=1.0` (see `LifeBar::clamp_values`),
+    /// the raw values being kept by default
+    pub clamp_life_bar: bool,
+    /// Reject frames with unknown key bits (see `Keys::from_bits_checked`),
+    /// instead of dropping these bits
+    pub strict_keys: bool,
+    /// When the LZMA stream of the replay data doesn't end exactly at its declared compressed
+    /// length (wrong in replays written by some broken tools), ignore the declared length and
+    /// decompress the replay data up to the end of its LZMA stream, falling back to the declared
+    /// length if there is no valid LZMA stream
+    pub lenient_compressed_length: bool,
+    /// Load replays with an unknown gamemode value as osu! Standard replays instead of failing,
+    /// the original value being reported in `Replay::warnings`
+    pub unknown_gamemode_as_std: bool,
+    /// Reject replays followed by extra bytes instead of keeping them in `Replay::trailing_bytes`,
+    /// to detect truncated or concatenated files
+    pub reject_trailing_bytes: bool,
+    /// Encoding of the player name, see `Encoding`
+    pub player_name_encoding: Encoding,
+}
+
+impl ParseOptions {
+    /// Range of the osu!stable game versions accepted by `check_game_version`
+    pub const GAME_VERSION_RANGE: core::ops::RangeInclusive<Integer> = 20070101..=21001231;
+    /// Range of the osu!lazer game versions accepted by `check_game_version`
+    pub const LAZER_GAME_VERSION_RANGE: core::ops::RangeInclusive<Integer> = 30000000..=30999999;
+
+    /// Whether the game version is accepted by `check_game_version`
+    pub fn is_plausible_game_version(version: Integer) -> bool {
+        Self::GAME_VERSION_RANGE.contains(&version)
+            || Self::LAZER_GAME_VERSION_RANGE.contains(&version)
+    }
+}
+
+/// Problem recovered from while parsing a replay, see `Replay::warnings`
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum ParseWarning {
+    /// Unknown gamemode value loaded as osu! Standard by `ParseOptions::unknown_gamemode_as_std`
+    UnknownGamemode { value: Byte },
+    /// Seed frame whose seed isn't a valid number, parsed as a `None` seed
+    MalformedSeed { frame: String },
+}
+
+/// Text encoding of a replay string
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Encoding {
+    /// UTF-8, strings which aren't valid UTF-8 failing the parsing
+    #[default]
+    Utf8,
+    /// UTF-8, falling back to Windows-1252 for the strings which aren't valid UTF-8
+    /// (written in this code page by some old osu! builds).
+    /// The fallback needs the `encoding` feature, `decode` failing on these strings without it.
+    Windows1252,
+}
+
+impl Encoding {
+    /// Decode the bytes of a string with this encoding
+    pub fn decode(&self, bytes: Vec<u8>) -> Result<String, Error> {
+        match self {
+            Encoding::Utf8 => String::from_utf8(bytes).map_err(|_| Error::CantReadString),
+            #[cfg(feature = "encoding")]
+            Encoding::Windows1252 => Ok(String::from_utf8(bytes).unwrap_or_else(|error| {
+                let (decoded, _) =
+                    encoding_rs::WINDOWS_1252.decode_without_bom_handling(error.as_bytes());
+                decoded.into_owned()
+            })),
+            #[cfg(not(feature = "encoding"))]
+            Encoding::Windows1252 => {
+                String::from_utf8(bytes).map_err(|_| Error::EncodingFeatureDisabled)
+            }
+        }
+    }
+}
@@ -1,58 +1,177 @@
-use super::*;
+use super::options::{ParseOptions, ParseWarning};
+use crate::error::Error;
+use crate::types::*;
+#[cfg(feature = "std")]
 use crate::utils::lzma::compress_replay_data;
+use alloc::collections::{BTreeMap, BinaryHeap};
+use alloc::format;
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
 use bitflags::bitflags;
+use core::fmt::{self, Display, Formatter};
+use core::ops::Range;
+use core::str::FromStr;
+#[cfg(feature = "std")]
+use std::collections::HashSet;
 
 /// Contains decompressed and parsed data of a replay
-#[derive(Debug, Default)]
+#[derive(Debug, Default, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct ReplayData {
     /// Parsed frames of the replay
     pub frames: Vec<ReplayFrame>,
     /// RNG seed used for the score
     /// Note: only available on replay file set on version '20130319' or later
     pub seed: Option<Integer>,
+    /// Whether the frames are currently flipped by `to_hardrock` (see `is_hardrock_applied`)
+    #[cfg_attr(feature = "serde", serde(default))]
+    hardrock_applied: bool,
+    /// Problems recovered from while parsing the replay data
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub warnings: Vec<ParseWarning>,
+    /// Original string of the seed frame kept by `ParseOptions::keep_seed_frame`,
+    /// written back as is while it holds the same seed (or a malformed one while
+    /// the seed is `None`)
+    #[cfg_attr(feature = "serde", serde(default))]
+    seed_frame: Option<String>,
+    /// Compressed replay data read from the replay file, with the checksum of the data it
+    /// decompressed to, written back as is while the data is unchanged
+    #[cfg(feature = "std")]
+    #[cfg_attr(feature = "serde", serde(skip))]
+    compressed: Option<(u64, Vec<u8>)>,
 }
 
 impl FromStr for ReplayData {
     type Err = Error;
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::parse_with_options(s, &ParseOptions::default())
+    }
+}
+
+impl ReplayData {
+    /// Parse the decompressed replay data string using the given parse options.
+    /// A leading UTF-8 BOM and whitespaces around the frames (ex. `\r\n` separators)
+    /// written by some third-party tools are ignored, and a malformed seed frame gives
+    /// a `None` seed instead of failing the whole parsing (reported in `warnings`).
+    pub fn parse_with_options(s: &str, options: &ParseOptions) -> Result<Self, Error> {
         let splitted_frames: Vec<&str> = s.split(',').collect();
 
         let mut seed = None;
+        let mut seed_frame = None;
+        let mut warnings = Vec::new();
         let mut frames: Vec<ReplayFrame> = Vec::new();
 
         for frame in splitted_frames.iter() {
-            // seed check
-            if frame.starts_with("-12345|0|0|") {
-                seed = Some(
-                    u32::from_str(frame.split('|').collect::<Vec<&str>>()[3])
-                        .map_err(|_| Error::CantParseFrameValue)?,
-                );
-                break;
+            match RawFrame::classify(frame) {
+                RawFrame::Seed {
+                    frame,
+                    seed: frame_seed,
+                } => {
+                    seed = frame_seed;
+                    if seed.is_none() {
+                        warnings.push(ParseWarning::MalformedSeed {
+                            frame: frame.to_string(),
+                        });
+                    }
+                    if options.keep_seed_frame {
+                        frames.push(ReplayFrame {
+                            w: ReplayFrame::SEED_FRAME_TIME,
+                            ..ReplayFrame::default()
+                        });
+                        seed_frame = Some(frame.to_string());
+                    }
+                    break;
+                }
+                RawFrame::Frame(frame) => {
+                    if let Ok(f) = ReplayFrame::parse_with_options(frame, options) {
+                        frames.push(f)
+                    }
+                }
             }
+        }
 
-            match ReplayFrame::from_str(frame) {
-                Ok(f) => frames.push(f),
-                Err(_) => (),
+        Ok(Self {
+            frames,
+            seed,
+            hardrock_applied: false,
+            warnings,
+            seed_frame,
+            #[cfg(feature = "std")]
+            compressed: None,
+        })
+    }
+}
+
+/// 64 bits FNV-1a hash of the bytes
+fn fnv1a(bytes: impl Iterator<Item = u8>) -> u64 {
+    const FNV_OFFSET_BASIS: u64 = 0xcbf29ce484222325;
+    const FNV_PRIME: u64 = 0x100000001b3;
+
+    bytes.fold(FNV_OFFSET_BASIS, |hash, byte| {
+        (hash ^ byte as u64).wrapping_mul(FNV_PRIME)
+    })
+}
+
+/// A frame string of the decompressed replay data
+pub(crate) enum RawFrame<'a> {
+    /// The `-12345|0|0|seed` frame ending the frames, with its seed if it is valid
+    Seed {
+        frame: &'a str,
+        seed: Option<Integer>,
+    },
+    /// Any other frame, to parse as a `ReplayFrame`
+    Frame(&'a str),
+}
+
+impl<'a> RawFrame<'a> {
+    /// Classify a frame string of the replay data, ignoring a leading UTF-8 BOM and
+    /// the whitespaces around the frame
+    pub(crate) fn classify(frame: &'a str) -> Self {
+        let frame = frame.strip_prefix('\u{feff}').unwrap_or(frame).trim();
+        if frame.starts_with("-12345|0|0|") {
+            RawFrame::Seed {
+                frame,
+                seed: frame
+                    .split('|')
+                    .nth(3)
+                    .and_then(|seed| Integer::from_str(seed).ok()),
             }
+        } else {
+            RawFrame::Frame(frame)
         }
-
-        Ok(Self { frames, seed })
     }
 }
 
 impl From<&ReplayData> for String {
     fn from(replay_data: &ReplayData) -> Self {
         let mut s = String::new();
+        // the original seed frame, unless the seed was changed since the parsing
+        let seed_frame = replay_data.seed_frame.as_deref().filter(|frame| {
+            matches!(RawFrame::classify(frame), RawFrame::Seed { seed, .. } if seed == replay_data.seed)
+        });
+        let mut seed_frame_written = false;
+        let has_seed_frame = replay_data.seed.is_some() || replay_data.seed_frame.is_some();
 
         // default first frames in each replay
         for frame in replay_data.frames.iter() {
+            if frame.w == ReplayFrame::SEED_FRAME_TIME && has_seed_frame {
+                // a kept seed frame is written from its original string, from the seed
+                // value below, or not at all once the seed is removed
+                if let (Some(seed_frame), false) = (seed_frame, seed_frame_written) {
+                    s.push_str(seed_frame);
+                    s.push(',');
+                    seed_frame_written = true;
+                }
+                continue;
+            }
             let frame_string: String = frame.into();
             s.push_str(&frame_string);
             s.push(',');
         }
 
         match replay_data.seed {
+            Some(_) if seed_frame_written => s,
             Some(seed) => {
                 s.push_str("-12345|0|0|");
                 s.push_str(&seed.to_string());
@@ -64,31 +183,877 @@ impl From<&ReplayData> for String {
     }
 }
 
+#[cfg(feature = "std")]
 impl TryFrom<&ReplayData> for Vec<u8> {
     type Error = Error;
 
     fn try_from(replay_data: &ReplayData) -> Result<Self, Error> {
-        let uncompressed = String::from(replay_data).as_bytes().to_vec();
-        compress_replay_data(uncompressed)
+        match &replay_data.compressed {
+            Some((checksum, compressed)) if *checksum == replay_data.data_checksum() => {
+                Ok(compressed.clone())
+            }
+            _ => replay_data.compress(),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl ReplayData {
+    /// Keep the compressed replay data this replay data was read from, to write it back as is
+    pub(crate) fn set_compressed(&mut self, compressed: Vec<u8>) {
+        self.compressed = Some((self.data_checksum(), compressed));
+    }
+
+    /// Compress the replay data, even when unchanged since it was read
+    pub(crate) fn compress(&self) -> Result<Vec<u8>, Error> {
+        compress_replay_data(String::from(self).into_bytes())
+    }
+
+    /// FNV-1a hash of the serialized replay data, seed included
+    fn data_checksum(&self) -> u64 {
+        fnv1a(String::from(self).bytes())
+    }
+
+    /// Compress the replay data into at most `max_bytes` bytes, keeping only one gameplay frame
+    /// out of 2, 4, 8... until the compressed data fits (the deltas of the removed frames being
+    /// added to the kept ones to keep the timing). Fails when it doesn't fit even with
+    /// a single gameplay frame left.
+    pub fn to_compressed_within(&self, max_bytes: usize) -> Result<Vec<u8>, Error> {
+        let gameplay_frame_count = self.gameplay_frames().len();
+        let mut step = 1;
+
+        loop {
+            let compressed: Vec<u8> = (&self.downsampled(step)).try_into()?;
+            if compressed.len() <= max_bytes {
+                return Ok(compressed);
+            }
+            if step >= gameplay_frame_count {
+                return Err(Error::CompressedSizeExceeded { max_bytes });
+            }
+            step *= 2;
+        }
+    }
+
+    /// Size in bytes of the LZMA-compressed replay data, as it would be written in a replay file
+    pub fn compressed_len(&self) -> Result<usize, Error> {
+        let compressed: Vec<u8> = self.try_into()?;
+        Ok(compressed.len())
+    }
+
+    /// Every distinct key combination pressed on the gameplay frames, to detect unusual inputs
+    /// (ex. mouse buttons used by a keyboard player). The no-key state is only included
+    /// when `include_empty` is set.
+    pub fn key_combinations_used(&self, include_empty: bool) -> HashSet<Keys> {
+        self.gameplay_frames()
+            .iter()
+            .map(|frame| frame.z)
+            .filter(|keys| include_empty || !keys.is_empty())
+            .collect()
+    }
+
+    /// Copy of the replay data keeping one gameplay frame out of `step`, and the last one
+    fn downsampled(&self, step: usize) -> ReplayData {
+        let range = self.gameplay_range();
+        let mut frames = Vec::with_capacity(self.frames.len() / step + 4);
+        let mut delta = 0;
+
+        for (i, frame) in self.frames.iter().enumerate() {
+            if !range.contains(&i) {
+                frames.push(*frame);
+                continue;
+            }
+            delta += frame.w;
+            let index = i - range.start;
+            if index.is_multiple_of(step) || i == range.end - 1 {
+                frames.push(ReplayFrame { w: delta, ..*frame });
+                delta = 0;
+            }
+        }
+
+        ReplayData {
+            frames,
+            ..self.clone()
+        }
     }
 }
 
 impl ReplayData {
+    /// Default sliding window used for clicks per second computations
+    pub const DEFAULT_CPS_WINDOW_MS: Long = 1000;
+    /// Duration of the consecutive windows checked by `spinner_segments`
+    pub const SPINNER_WINDOW_MS: Long = 500;
+    /// Minimum rotation speed around the window centroid for `spinner_segments`
+    pub const SPINNER_MIN_ROTATIONS_PER_SECOND: f64 = 2.0;
+    /// Minimum mean distance (in osu! pixels) to the window centroid for `spinner_segments`
+    pub const SPINNER_MIN_RADIUS: f64 = 10.0;
+    /// Maximum standard deviation of the distance to the window centroid for `spinner_segments`,
+    /// relative to the mean distance
+    pub const SPINNER_MAX_RADIUS_DEVIATION: f64 = 0.35;
+    /// Largest cursor move (in osu! pixels, on each axis) still considered static
+    /// by `coalesce_static_frames`
+    pub const STATIC_FRAME_EPSILON: Float = 0.01;
+    /// Longest interval (in milliseconds) between two key-downs counted as stream taps
+    /// by `estimated_stream_bpm`, 1/4 notes at 100 BPM
+    pub const STREAM_MAX_TAP_INTERVAL_MS: Long = 150;
+    /// Minimum number of stream tap intervals needed by `estimated_stream_bpm`
+    pub const STREAM_MIN_TAPS: usize = 16;
+
     pub fn new() -> Self {
         Self::default()
     }
 
+    /// Frames of the actual gameplay, skipping:
+    /// - the leading convention frames written by osu! before the gameplay,
+    ///   placed outside of the playfield at `(256, -500)`
+    /// - the trailing seed frame (`-12345|0|0|seed`) if it was kept in the frames
+    pub fn gameplay_frames(&self) -> &[ReplayFrame] {
+        &self.frames[self.gameplay_range()]
+    }
+
+    /// Same as `gameplay_frames`, to transform the gameplay frames
+    /// while keeping the convention and seed frames untouched
+    fn gameplay_frames_mut(&mut self) -> &mut [ReplayFrame] {
+        let range = self.gameplay_range();
+        &mut self.frames[range]
+    }
+
+    /// Indexes of the gameplay frames in `frames` (see `gameplay_frames`)
+    fn gameplay_range(&self) -> Range<usize> {
+        let start = self
+            .frames
+            .iter()
+            .take_while(|frame| frame.is_convention_frame())
+            .count();
+        let end = match self.frames.last() {
+            Some(frame) if start < self.frames.len() && frame.w == ReplayFrame::SEED_FRAME_TIME => {
+                self.frames.len() - 1
+            }
+            _ => self.frames.len(),
+        };
+
+        start..end
+    }
+
+    /// Gameplay frames with their absolute time in milliseconds (see `gameplay_frames`)
+    pub(crate) fn timed_gameplay_frames(&self) -> impl Iterator<Item = (Long, &ReplayFrame)> {
+        let range = self.gameplay_range();
+        self.timed_frames().skip(range.start).take(range.len())
+    }
+
+    /// Absolute times (in milliseconds) of the first and last gameplay frames,
+    /// or `(0, 0)` when there is no gameplay frame
+    pub(crate) fn play_window(&self) -> (Long, Long) {
+        let mut times = self.timed_gameplay_frames().map(|(time, _)| time);
+
+        match times.next() {
+            Some(start) => (start, times.last().unwrap_or(start)),
+            None => (0, 0),
+        }
+    }
+
+    /// Compare with another replay data, tolerating a difference of up to `epsilon` on the
+    /// `x` and `y` frame values (ex. caused by float formatting when re-parsing),
+    /// the seed and the `w` and `z` values being compared exactly
+    pub fn approx_eq(&self, other: &ReplayData, epsilon: f32) -> bool {
+        self.seed == other.seed
+            && self.frames.len() == other.frames.len()
+            && self.frames.iter().zip(other.frames.iter()).all(|(a, b)| {
+                a.w == b.w
+                    && a.z == b.z
+                    && (a.x - b.x).abs() <= epsilon
+                    && (a.y - b.y).abs() <= epsilon
+            })
+    }
+
+    /// Fast non-cryptographic hash (64 bits FNV-1a) of the serialized frames,
+    /// for cheap equality checks and caching of frame sets
+    pub fn frames_checksum(&self) -> u64 {
+        fnv1a(self.frames.iter().flat_map(|frame| {
+            let frame_string: String = frame.into();
+            frame_string
+                .into_bytes()
+                .into_iter()
+                .chain(core::iter::once(b','))
+        }))
+    }
+
+    /// Time deltas (in milliseconds) of the gameplay frames (see `gameplay_frames`),
+    /// without the negative deltas, to be used by the frame statistics
+    pub fn gameplay_deltas(&self) -> Vec<Long> {
+        self.gameplay_frames()
+            .iter()
+            .map(|frame| frame.w)
+            .filter(|delta| *delta >= 0)
+            .collect()
+    }
+
+    /// Estimate the poll rate (in Hz) of the input device from the most common frame delta,
+    /// ignoring zero and negative deltas (ex. ~1000Hz for a tablet, ~125Hz for a basic mouse).
+    /// Returns 0 when there is no positive delta.
+    pub fn estimated_poll_rate_hz(&self) -> f64 {
+        let mut deltas: BTreeMap<Long, u32> = BTreeMap::new();
+        for delta in self
+            .gameplay_deltas()
+            .into_iter()
+            .filter(|delta| *delta > 0)
+        {
+            *deltas.entry(delta).or_default() += 1;
+        }
+
+        match deltas.iter().max_by_key(|(_, count)| **count) {
+            Some((delta, _)) => 1000.0 / *delta as f64,
+            None => 0.0,
+        }
+    }
+
+    /// Shannon entropy of the distribution of the frame deltas (see `gameplay_deltas`), as a
+    /// bot detection metric: human inputs have irregular deltas while replay bots tend to send
+    /// frames at a perfectly regular interval. The entropy in bits is normalized by its maximum
+    /// `log2(delta count)` (every delta being different) to be in `0.0..=1.0` whatever the length
+    /// of the replay. Returns 0 with less than 2 deltas.
+    #[cfg(feature = "std")]
+    pub fn input_entropy(&self) -> f64 {
+        let deltas = self.gameplay_deltas();
+        if deltas.len() < 2 {
+            return 0.0;
+        }
+
+        let mut counts: BTreeMap<Long, u32> = BTreeMap::new();
+        for delta in &deltas {
+            *counts.entry(*delta).or_default() += 1;
+        }
+
+        let total = deltas.len() as f64;
+        let entropy: f64 = counts
+            .values()
+            .map(|count| {
+                let probability = *count as f64 / total;
+                -probability * probability.log2()
+            })
+            .sum();
+
+        entropy / total.log2()
+    }
+
+    /// Total path length of the cursor over the gameplay frames, in osu! pixels
+    #[cfg(feature = "std")]
+    pub fn total_distance(&self) -> f64 {
+        self.gameplay_frames()
+            .windows(2)
+            .map(|pair| {
+                let dx = (pair[1].x - pair[0].x) as f64;
+                let dy = (pair[1].y - pair[0].y) as f64;
+                dx.hypot(dy)
+            })
+            .sum()
+    }
+
+    /// Rough difficulty proxy of the cursor movement, to categorize replays without the beatmap.
+    /// This is not a star rating: it's the average cursor speed (total path length of the
+    /// gameplay frames in osu! pixels divided by their duration in milliseconds), scaled by
+    /// `1 + direction changes per second`, a direction change being a turn of more than 90°.
+    /// Returns 0 when the gameplay frames have no duration.
+    #[cfg(feature = "std")]
+    pub fn movement_complexity(&self) -> f64 {
+        let frames = self.gameplay_frames();
+        let mut path_length = 0.0;
+        let mut duration = 0.0;
+        let mut direction_changes = 0;
+        let mut previous_move: Option<(f64, f64)> = None;
+
+        for pair in frames.windows(2) {
+            let dx = (pair[1].x - pair[0].x) as f64;
+            let dy = (pair[1].y - pair[0].y) as f64;
+            if pair[1].w > 0 {
+                duration += pair[1].w as f64;
+            }
+            if dx == 0.0 && dy == 0.0 {
+                continue;
+            }
+            path_length += (dx * dx + dy * dy).sqrt();
+            if let Some((px, py)) = previous_move {
+                if px * dx + py * dy < 0.0 {
+                    direction_changes += 1;
+                }
+            }
+            previous_move = Some((dx, dy));
+        }
+
+        if duration <= 0.0 {
+            return 0.0;
+        }
+        let average_speed = path_length / duration;
+        let direction_changes_per_second = direction_changes as f64 * 1000.0 / duration;
+
+        average_speed * (1.0 + direction_changes_per_second)
+    }
+
+    /// Mean cursor position `(x, y)` of the gameplay frames, or `(0, 0)` without gameplay frames.
+    /// Useful to detect replays staying in one region of the playfield.
+    pub fn centroid(&self) -> (f32, f32) {
+        Self::weighted_centroid(self.gameplay_frames().iter().map(|frame| (frame, 1.0)))
+    }
+
+    /// Same as `centroid`, each position being weighted by the time the cursor stayed there
+    /// (the delta of the next frame), so positions held longer weigh more
+    pub fn time_weighted_centroid(&self) -> (f32, f32) {
+        let frames = self.gameplay_frames();
+        let weights = frames.iter().skip(1).map(|frame| frame.w.max(0) as f64);
+
+        Self::weighted_centroid(frames.iter().zip(weights))
+    }
+
+    fn weighted_centroid<'a>(frames: impl Iterator<Item = (&'a ReplayFrame, f64)>) -> (f32, f32) {
+        let (mut x, mut y, mut total) = (0.0, 0.0, 0.0);
+        for (frame, weight) in frames {
+            x += frame.x as f64 * weight;
+            y += frame.y as f64 * weight;
+            total += weight;
+        }
+
+        if total == 0.0 {
+            return (0.0, 0.0);
+        }
+        ((x / total) as f32, (y / total) as f32)
+    }
+
+    /// Absolute time ranges `(start, end)` (in milliseconds) where the cursor rotates quickly
+    /// around a point, approximating the spinners of the play.
+    /// The gameplay frames are split into consecutive windows of `SPINNER_WINDOW_MS`, a window
+    /// being a spinning one when the cursor rotates around the centroid of the window at
+    /// `SPINNER_MIN_ROTATIONS_PER_SECOND` or more, at a mean distance of `SPINNER_MIN_RADIUS`
+    /// or more varying by at most `SPINNER_MAX_RADIUS_DEVIATION`.
+    /// Consecutive spinning windows are merged into a single range.
+    #[cfg(feature = "std")]
+    pub fn spinner_segments(&self) -> Vec<(Long, Long)> {
+        let range = self.gameplay_range();
+        let frames: Vec<(Long, &ReplayFrame)> = self
+            .timed_frames()
+            .skip(range.start)
+            .take(range.len())
+            .collect();
+        let mut segments: Vec<(Long, Long)> = Vec::new();
+        let mut previous_spinning = false;
+        let mut start = 0;
+
+        while start < frames.len() {
+            let window_start = frames[start].0;
+            let end = start
+                + frames[start..]
+                    .iter()
+                    .take_while(|(time, _)| *time - window_start < Self::SPINNER_WINDOW_MS)
+                    .count();
+            let window = &frames[start..end];
+            let window_end = window[window.len() - 1].0;
+
+            let spinning = Self::is_spinning(window);
+            if spinning {
+                match segments.last_mut() {
+                    Some(segment) if previous_spinning => segment.1 = window_end,
+                    _ => segments.push((window_start, window_end)),
+                }
+            }
+            previous_spinning = spinning;
+            start = end;
+        }
+
+        segments
+    }
+
+    #[cfg(feature = "std")]
+    fn is_spinning(window: &[(Long, &ReplayFrame)]) -> bool {
+        let duration = window[window.len() - 1].0 - window[0].0;
+        if window.len() < 3 || duration <= 0 {
+            return false;
+        }
+
+        let count = window.len() as f64;
+        let cx = window.iter().map(|(_, frame)| frame.x as f64).sum::<f64>() / count;
+        let cy = window.iter().map(|(_, frame)| frame.y as f64).sum::<f64>() / count;
+        let vectors: Vec<(f64, f64)> = window
+            .iter()
+            .map(|(_, frame)| (frame.x as f64 - cx, frame.y as f64 - cy))
+            .collect();
+
+        let radii: Vec<f64> = vectors.iter().map(|(x, y)| x.hypot(*y)).collect();
+        let mean_radius = radii.iter().sum::<f64>() / count;
+        if mean_radius < Self::SPINNER_MIN_RADIUS {
+            return false;
+        }
+        let variance = radii
+            .iter()
+            .map(|radius| (radius - mean_radius).powi(2))
+            .sum::<f64>()
+            / count;
+        if variance.sqrt() / mean_radius > Self::SPINNER_MAX_RADIUS_DEVIATION {
+            return false;
+        }
+
+        let swept_angle: f64 = vectors
+            .windows(2)
+            .map(|pair| {
+                let ((x1, y1), (x2, y2)) = (pair[0], pair[1]);
+                (x1 * y2 - y1 * x2).atan2(x1 * x2 + y1 * y2)
+            })
+            .sum();
+        let rotations_per_second =
+            swept_angle.abs() / core::f64::consts::TAU * 1000.0 / duration as f64;
+
+        rotations_per_second >= Self::SPINNER_MIN_ROTATIONS_PER_SECOND
+    }
+
+    /// Gameplay frames as a `N x 3` array of `(absolute time, x, y)` rows,
+    /// the absolute time being in milliseconds
+    #[cfg(feature = "ndarray")]
+    pub fn to_ndarray(&self) -> ndarray::Array2<f32> {
+        let range = self.gameplay_range();
+        let values: Vec<f32> = self
+            .timed_frames()
+            .skip(range.start)
+            .take(range.len())
+            .flat_map(|(time, frame)| [time as f32, frame.x, frame.y])
+            .collect();
+
+        ndarray::Array2::from_shape_vec((range.len(), 3), values)
+            .expect("3 values are collected for each frame")
+    }
+
+    /// Gameplay frames as `(time, x, y)`, the time being the absolute time of the frame
+    /// shifted by `audio_offset_ms`, to sync the frames with the offset of a specific client
+    pub fn aligned_frames(&self, audio_offset_ms: Long) -> Vec<(Long, f32, f32)> {
+        let range = self.gameplay_range();
+        self.timed_frames()
+            .skip(range.start)
+            .take(range.len())
+            .map(|(time, frame)| (time + audio_offset_ms, frame.x, frame.y))
+            .collect()
+    }
+
+    /// Time ranges `(start, end)` of the gameplay frames whose delta exceeds `threshold_ms`,
+    /// which happen when the game was paused or stopped sending frames
+    pub fn pause_segments(&self, threshold_ms: Long) -> Vec<(Long, Long)> {
+        let range = self.gameplay_range();
+        self.timed_frames()
+            .skip(range.start)
+            .take(range.len())
+            .filter(|(_, frame)| frame.w > threshold_ms)
+            .map(|(time, frame)| (time - frame.w, time))
+            .collect()
+    }
+
+    /// Resample the gameplay frames at `hz` samples per second as `(x, y, keys)`, for tools
+    /// needing uniformly spaced cursor data. There are `duration × hz` samples (the duration
+    /// between the first and the last gameplay frames, in seconds), the first one being at the
+    /// time of the first gameplay frame. Positions are linearly interpolated between the
+    /// surrounding frames, while the keys are those of the last frame at or before the sample,
+    /// a key state being held until the next frame.
+    pub fn resample_frames(&self, hz: u32) -> Vec<(f32, f32, u32)> {
+        let range = self.gameplay_range();
+        let frames: Vec<(Long, &ReplayFrame)> = self
+            .timed_frames()
+            .skip(range.start)
+            .take(range.len())
+            .collect();
+        let (Some((start, _)), Some((end, _))) = (frames.first(), frames.last()) else {
+            return Vec::new();
+        };
+
+        let sample_count = ((end - start).max(0) as u64 * hz as u64 / 1000) as usize;
+        let mut samples = Vec::with_capacity(sample_count);
+        let mut index = 0;
+
+        for i in 0..sample_count {
+            let time = *start as f64 + i as f64 * 1000.0 / hz as f64;
+            while index + 1 < frames.len() && frames[index + 1].0 as f64 <= time {
+                index += 1;
+            }
+            let (previous_time, previous) = frames[index];
+            let (x, y) = match frames.get(index + 1) {
+                Some(&(next_time, next)) if next_time > previous_time => {
+                    let ratio = ((time - previous_time as f64) / (next_time - previous_time) as f64)
+                        .clamp(0.0, 1.0) as f32;
+                    (
+                        previous.x + (next.x - previous.x) * ratio,
+                        previous.y + (next.y - previous.y) * ratio,
+                    )
+                }
+                _ => (previous.x, previous.y),
+            };
+            samples.push((x, y, previous.z.bits()));
+        }
+
+        samples
+    }
+
+    /// Simplify the cursor path of the gameplay frames into at most `max_points` positions to
+    /// render it, keeping the first and last frames and the frames where the keys change.
+    /// The other points are added Douglas-Peucker style: the point farthest from the simplified
+    /// path is added until `max_points` is reached or the remaining points are on the path.
+    /// With more key changes than `max_points`, evenly spaced key changes are kept.
+    pub fn decimate_for_render(&self, max_points: usize) -> Vec<(f32, f32)> {
+        let frames = self.gameplay_frames();
+        let mut kept: Vec<usize> = (0..frames.len())
+            .filter(|&i| i == 0 || i == frames.len() - 1 || frames[i].z != frames[i - 1].z)
+            .collect();
+
+        if kept.len() >= max_points {
+            let step = kept.len() as f64 / max_points as f64;
+            return (0..max_points)
+                .map(|i| {
+                    let frame = &frames[kept[(i as f64 * step) as usize]];
+                    (frame.x, frame.y)
+                })
+                .collect();
+        }
+
+        let mut candidates = BinaryHeap::new();
+        for pair in kept.windows(2) {
+            if let Some(candidate) = Self::farthest_point(frames, pair[0], pair[1]) {
+                candidates.push(candidate);
+            }
+        }
+        while kept.len() < max_points {
+            let Some((_, start, index, end)) = candidates.pop() else {
+                break;
+            };
+            kept.push(index);
+            candidates.extend(Self::farthest_point(frames, start, index));
+            candidates.extend(Self::farthest_point(frames, index, end));
+        }
+
+        kept.sort_unstable();
+        kept.iter().map(|&i| (frames[i].x, frames[i].y)).collect()
+    }
+
+    /// Point between the frames `start` and `end` (excluded) farthest from the segment joining
+    /// them, as `(squared distance bits, start, index, end)`, or `None` if every point is on it
+    fn farthest_point(
+        frames: &[ReplayFrame],
+        start: usize,
+        end: usize,
+    ) -> Option<(u64, usize, usize, usize)> {
+        let (ax, ay) = (frames[start].x as f64, frames[start].y as f64);
+        let (dx, dy) = (frames[end].x as f64 - ax, frames[end].y as f64 - ay);
+        let length_squared = dx * dx + dy * dy;
+
+        (start + 1..end)
+            .map(|i| {
+                let (px, py) = (frames[i].x as f64 - ax, frames[i].y as f64 - ay);
+                let distance_squared = if length_squared > 0.0 {
+                    let cross = dx * py - dy * px;
+                    cross * cross / length_squared
+                } else {
+                    px * px + py * py
+                };
+                // the bits of positive floats are ordered like the floats
+                (distance_squared.to_bits(), start, i, end)
+            })
+            .filter(|(distance_bits, ..)| *distance_bits > 0)
+            .max()
+    }
+
+    /// Keys held at the given absolute time (in milliseconds): the keys of the last frame
+    /// at or before this time, or no key before the first frame
+    pub fn keys_at(&self, time_ms: Long) -> Keys {
+        self.timed_frames()
+            .filter(|(time, frame)| frame.w != ReplayFrame::SEED_FRAME_TIME && *time <= time_ms)
+            .last()
+            .map_or(Keys::empty(), |(_, frame)| frame.z)
+    }
+
+    /// Remove the gameplay frames that don't change the keys or move the cursor by more than
+    /// `STATIC_FRAME_EPSILON` since the previous kept frame (ex. written by high poll rate
+    /// devices), adding their delta to the next kept frame to keep the timing.
+    /// The last gameplay frame is always kept so the duration is unchanged.
+    pub fn coalesce_static_frames(&mut self) {
+        let range = self.gameplay_range();
+        let mut frames = Vec::with_capacity(self.frames.len());
+        frames.extend_from_slice(&self.frames[..range.start]);
+        let mut previous: Option<ReplayFrame> = None;
+        let mut delta = 0;
+
+        for (i, frame) in self.frames[range.clone()].iter().enumerate() {
+            delta += frame.w;
+            let is_static = previous.is_some_and(|previous| {
+                previous.z == frame.z
+                    && (previous.x - frame.x).abs() <= Self::STATIC_FRAME_EPSILON
+                    && (previous.y - frame.y).abs() <= Self::STATIC_FRAME_EPSILON
+            });
+            if is_static && i < range.len() - 1 {
+                continue;
+            }
+            frames.push(ReplayFrame { w: delta, ..*frame });
+            previous = Some(*frame);
+            delta = 0;
+        }
+
+        frames.extend_from_slice(&self.frames[range.end..]);
+        self.frames = frames;
+    }
+
+    /// Frames as `(absolute time, x, y, keys)` samples, without the seed frame if it was kept
+    pub fn frames_absolute(&self) -> Vec<(Long, f32, f32, Integer)> {
+        self.timed_frames()
+            .filter(|(_, frame)| frame.w != ReplayFrame::SEED_FRAME_TIME)
+            .map(|(time, frame)| (time, frame.x, frame.y, frame.z.bits()))
+            .collect()
+    }
+
+    /// Build a replay data from `(absolute time, x, y, keys)` samples, the inverse of
+    /// `frames_absolute`. The delta of the first frame is its absolute time,
+    /// and unknown key bits are dropped.
+    pub fn from_samples(samples: &[(Long, f32, f32, Integer)]) -> ReplayData {
+        let mut previous_time = 0;
+        let frames = samples
+            .iter()
+            .map(|&(time, x, y, keys)| {
+                let frame = ReplayFrame {
+                    w: time - previous_time,
+                    x,
+                    y,
+                    z: Keys::from_bits_truncate(keys),
+                };
+                previous_time = time;
+                frame
+            })
+            .collect();
+
+        ReplayData {
+            frames,
+            ..ReplayData::default()
+        }
+    }
+
+    /// Frames with their absolute time in milliseconds (sum of the previous deltas)
+    fn timed_frames(&self) -> impl Iterator<Item = (Long, &ReplayFrame)> {
+        self.frames.iter().scan(0, |time, frame| {
+            *time += frame.w;
+            Some((*time, frame))
+        })
+    }
+
+    /// Extract the frames between `start_ms` and `end_ms` (absolute times, inclusive)
+    /// into a new replay data keeping the same seed.
+    /// The time of the first extracted frame is made relative to `start_ms`.
+    pub fn slice_time(&self, start_ms: Long, end_ms: Long) -> ReplayData {
+        let mut frames: Vec<ReplayFrame> = Vec::new();
+
+        for (time, frame) in self.timed_frames() {
+            if time < start_ms || time > end_ms {
+                continue;
+            }
+            let mut frame = *frame;
+            if frames.is_empty() {
+                frame.w = time - start_ms;
+            }
+            frames.push(frame);
+        }
+
+        ReplayData {
+            frames,
+            seed: self.seed,
+            hardrock_applied: self.hardrock_applied,
+            warnings: Vec::new(),
+            seed_frame: None,
+            #[cfg(feature = "std")]
+            compressed: None,
+        }
+    }
+
+    /// Absolute times (in milliseconds) of every key-down event.
+    /// A key-down is counted when M1 or M2 gets pressed (K1/K2 are always set along with them).
+    fn key_presses(&self) -> Vec<Long> {
+        let mut presses = Vec::new();
+        let mut previous = Keys::empty();
+
+        for (time, frame) in self.timed_frames() {
+            let buttons = frame.z & (Keys::M1 | Keys::M2);
+            let pressed = buttons - previous;
+            for _ in 0..pressed.bits().count_ones() {
+                presses.push(time);
+            }
+            previous = buttons;
+        }
+
+        presses
+    }
+
+    /// Compute a sliding-window clicks per second series from the key-down events,
+    /// as `(time, clicks per second)` pairs, with one pair for each key-down.
+    /// The clicks are counted in the `window_ms` milliseconds up to each key-down
+    /// and scaled to a per-second rate (see `DEFAULT_CPS_WINDOW_MS` for a reasonable window).
+    /// Returns an empty series for a window of 0 or less.
+    pub fn clicks_per_second(&self, window_ms: Long) -> Vec<(Long, u32)> {
+        if window_ms <= 0 {
+            return Vec::new();
+        }
+
+        let presses = self.key_presses();
+        let mut series = Vec::with_capacity(presses.len());
+        let mut window_start = 0;
+
+        for (i, &time) in presses.iter().enumerate() {
+            while window_start < i && presses[window_start] <= time - window_ms {
+                window_start += 1;
+            }
+            let count = (i - window_start + 1) as Long;
+            series.push((time, (count * 1000 / window_ms) as u32));
+        }
+
+        series
+    }
+
+    /// Split the gameplay frames into segments of continuously held keys.
+    /// A new segment starts each time the held keys change, so overlapping presses
+    /// (ex. K1 held, then K2 pressed, then K1 released) give a segment for each combination.
+    /// A segment still held on the last frame ends at the time of that frame.
+    pub fn press_segments(&self) -> Vec<PressSegment> {
+        let range = self.gameplay_range();
+        let mut segments = Vec::new();
+        let mut current: Option<PressSegment> = None;
+        let mut last_time = 0;
+
+        for (time, frame) in self.timed_frames().skip(range.start).take(range.len()) {
+            last_time = time;
+            if current.is_some_and(|segment| segment.keys == frame.z) {
+                continue;
+            }
+            if let Some(segment) = current.take() {
+                segments.push(PressSegment {
+                    end: time,
+                    ..segment
+                });
+            }
+            if !frame.z.is_empty() {
+                current = Some(PressSegment {
+                    start: time,
+                    end: time,
+                    keys: frame.z,
+                });
+            }
+        }
+
+        if let Some(segment) = current {
+            segments.push(PressSegment {
+                end: last_time,
+                ..segment
+            });
+        }
+
+        segments
+    }
+
+    /// Estimate the BPM of the streams (1/4 notes) played, from the most common interval between
+    /// key-downs at most `STREAM_MAX_TAP_INTERVAL_MS` apart: the intervals within 2 milliseconds of
+    /// the most common one are averaged, and the BPM is `15000 / interval`.
+    /// Returns `None` with less than `STREAM_MIN_TAPS` of these intervals.
+    pub fn estimated_stream_bpm(&self) -> Option<f64> {
+        let intervals: Vec<Long> = self
+            .key_presses()
+            .windows(2)
+            .map(|pair| pair[1] - pair[0])
+            .filter(|interval| (1..=Self::STREAM_MAX_TAP_INTERVAL_MS).contains(interval))
+            .collect();
+        if intervals.len() < Self::STREAM_MIN_TAPS {
+            return None;
+        }
+
+        let mut counts: BTreeMap<Long, u32> = BTreeMap::new();
+        for &interval in &intervals {
+            *counts.entry(interval).or_default() += 1;
+        }
+        let (most_common, _) = counts.iter().max_by_key(|(_, count)| **count)?;
+
+        let close: Vec<Long> = intervals
+            .into_iter()
+            .filter(|interval| (interval - most_common).abs() <= 2)
+            .collect();
+        let average = close.iter().sum::<Long>() as f64 / close.len() as f64;
+
+        Some(15000.0 / average)
+    }
+
+    /// Flip the gameplay frames vertically like the HardRock mod does, the convention and seed
+    /// frames being kept as is. Applying it twice restores the original frames,
+    /// see `is_hardrock_applied`.
     pub fn to_hardrock(&mut self) {
-        for frame in self.frames.iter_mut() {
-            frame.reverse()
+        self.to_hardrock_with_playfield(&Playfield::default())
+    }
+
+    /// Same as `to_hardrock`, flipping the frames across the center of the given playfield
+    pub fn to_hardrock_with_playfield(&mut self, playfield: &Playfield) {
+        for frame in self.gameplay_frames_mut() {
+            frame.reverse(playfield)
+        }
+        self.hardrock_applied = !self.hardrock_applied;
+    }
+
+    /// Whether the frames are currently flipped by an odd number of `to_hardrock`
+    /// (or `apply_mod_transform` with HardRock) calls since they were parsed,
+    /// to avoid flipping them twice by mistake
+    pub fn is_hardrock_applied(&self) -> bool {
+        self.hardrock_applied
+    }
+
+    /// Apply the geometric transforms of the given mods to the gameplay frames (see
+    /// `gameplay_frames`), according to the gamemode:
+    /// - osu! Standard: HardRock flips the cursor vertically, Mirror flips it horizontally.
+    /// - osu!mania: Mirror flips the columns (the `x` value holds the pressed columns),
+    ///   the key count is taken from the KEY mods or guessed from the highest pressed column.
+    ///   HardRock has no geometric effect.
+    /// - Taiko and Catch the Beat: no geometric transform is applied.
+    pub fn apply_mod_transform(&mut self, mods: Mods, gamemode: Gamemode) {
+        self.apply_mod_transform_with_playfield(mods, gamemode, &Playfield::default())
+    }
+
+    /// Same as `apply_mod_transform`, the osu! Standard flips being done on the given playfield
+    pub fn apply_mod_transform_with_playfield(
+        &mut self,
+        mods: Mods,
+        gamemode: Gamemode,
+        playfield: &Playfield,
+    ) {
+        match gamemode {
+            Gamemode::STD => {
+                if mods.contains(Mods::HARDROCK) {
+                    self.to_hardrock_with_playfield(playfield);
+                }
+                if mods.contains(Mods::MIRROR) {
+                    for frame in self.gameplay_frames_mut() {
+                        frame.mirror(playfield)
+                    }
+                }
+            }
+            Gamemode::MANIA => {
+                if mods.contains(Mods::MIRROR) {
+                    let key_count = match mods.mania_key_count() {
+                        Some(key_count) => key_count,
+                        None => self.highest_column() + 1,
+                    };
+                    for frame in self.gameplay_frames_mut() {
+                        frame.mirror_columns(key_count)
+                    }
+                }
+            }
+            Gamemode::TAIKO | Gamemode::CTB => (),
         }
     }
+
+    fn highest_column(&self) -> u32 {
+        self.gameplay_frames()
+            .iter()
+            .filter(|frame| frame.x > 0.0)
+            .map(|frame| 31 - (frame.x as u32).leading_zeros())
+            .max()
+            .unwrap_or_default()
+    }
 }
 
 ////////////////////////////////////////////////////////////////////////////////////////////////////
 
 /// Parsed data of a frame replay data
 #[derive(Debug, Clone, Copy, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct ReplayFrame {
     /// Time in milliseconds since the previous action
     pub w: Long,
@@ -106,23 +1071,44 @@ impl FromStr for ReplayFrame {
     type Err = Error;
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::parse_with_options(s, &ParseOptions::default())
+    }
+}
+
+impl ReplayFrame {
+    /// Parse a `w|x|y|z` frame string using the given parse options
+    pub fn parse_with_options(s: &str, options: &ParseOptions) -> Result<Self, Error> {
         let splitted_event: Vec<&str> = s.split('|').collect();
 
-        if splitted_event.len() != 4 {
-            return Err(Error::InvalidStringFrameFormat);
+        match splitted_event.len() {
+            4 => (),
+            len if len > 4 && options.lenient_frames => (),
+            _ => return Err(Error::InvalidStringFrameFormat),
         }
 
+        let keys = Integer::from_str(splitted_event[3]).map_err(|_| Error::CantParseFrameValue)?;
         let frame = Self {
             w: Long::from_str(splitted_event[0]).map_err(|_| Error::CantParseFrameValue)?,
-            x: f32::from_str(splitted_event[1]).map_err(|_| Error::CantParseFrameValue)?,
-            y: f32::from_str(splitted_event[2]).map_err(|_| Error::CantParseFrameValue)?,
-            z: Keys::from_bits_truncate(
-                Integer::from_str(splitted_event[3]).map_err(|_| Error::CantParseFrameValue)?,
-            ),
+            x: Self::parse_coordinate(splitted_event[1])?,
+            y: Self::parse_coordinate(splitted_event[2])?,
+            z: if options.strict_keys {
+                Keys::from_bits_checked(keys)?
+            } else {
+                Keys::from_bits_truncate(keys)
+            },
         };
 
         Ok(frame)
     }
+
+    /// Parse a cursor coordinate, rejecting the non-finite values (`NaN`, `inf`)
+    /// accepted by `f32::from_str` but never written by osu!
+    fn parse_coordinate(s: &str) -> Result<Float, Error> {
+        match Float::from_str(s) {
+            Ok(value) if value.is_finite() => Ok(value),
+            _ => Err(Error::CantParseFrameValue),
+        }
+    }
 }
 
 impl From<&ReplayFrame> for String {
@@ -136,29 +1122,111 @@ impl ReplayFrame {
     pub const CENTER_X: f32 = Self::MAX_X / 2.0;
     pub const MAX_Y: f32 = 384.0;
     pub const CENTER_Y: f32 = Self::MAX_Y / 2.0;
+    /// Time value of the special frame holding the RNG seed
+    pub const SEED_FRAME_TIME: Long = -12345;
 
     pub fn new() -> Self {
         Self::default()
     }
 
-    fn reverse(&mut self) {
-        if self.y > Self::CENTER_Y {
-            let diff = self.y - Self::CENTER_Y;
-            self.y = self.y - diff * 2.0;
+    /// Number of keys/mouse buttons pressed on this frame (M1, M2, K1 and K2, Smoke excluded)
+    pub fn active_key_count(&self) -> u32 {
+        (self.z - Keys::SMOKE).bits().count_ones()
+    }
+
+    /// Press or release the given keys on this frame
+    pub fn set_key(&mut self, key: Keys, pressed: bool) {
+        self.z.set(key, pressed)
+    }
+
+    /// Same frame with exactly the given keys pressed
+    pub fn with_keys(self, keys: Keys) -> Self {
+        Self { z: keys, ..self }
+    }
+
+    fn is_convention_frame(&self) -> bool {
+        self.x == 256.0 && self.y == -500.0
+    }
+
+    /// Position of the cursor relative to the size of the given playfield
+    /// (0 - 1 when the cursor is inside the playfield)
+    pub fn normalize(&self, playfield: &Playfield) -> (Float, Float) {
+        (self.x / playfield.width, self.y / playfield.height)
+    }
+
+    fn reverse(&mut self, playfield: &Playfield) {
+        let center_y = playfield.height / 2.0;
+
+        if self.y > center_y {
+            let diff = self.y - center_y;
+            self.y -= diff * 2.0;
             return;
         }
-        if self.y < Self::CENTER_Y {
-            let diff = Self::CENTER_Y - self.y;
-            self.y = self.y + diff * 2.0;
-            return;
+        if self.y < center_y {
+            let diff = center_y - self.y;
+            self.y += diff * 2.0;
         }
     }
+
+    fn mirror(&mut self, playfield: &Playfield) {
+        self.x = playfield.width - self.x;
+    }
+
+    fn mirror_columns(&mut self, key_count: u32) {
+        let columns = self.x as u32;
+        let mut mirrored = 0;
+
+        for column in 0..key_count {
+            if columns & (1 << column) != 0 {
+                mirrored |= 1 << (key_count - 1 - column);
+            }
+        }
+
+        self.x = mirrored as Float;
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////////////////////////
+
+/// Coordinate space of the cursor positions, osu! playfield of 512x384 by default
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Playfield {
+    pub width: Float,
+    pub height: Float,
+}
+
+impl Playfield {
+    pub fn new(width: Float, height: Float) -> Self {
+        Self { width, height }
+    }
+}
+
+impl Default for Playfield {
+    fn default() -> Self {
+        Self {
+            width: ReplayFrame::MAX_X,
+            height: ReplayFrame::MAX_Y,
+        }
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////////////////////////
+
+/// Time range (absolute times in milliseconds) during which the same keys were held
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct PressSegment {
+    pub start: Long,
+    pub end: Long,
+    pub keys: Keys,
 }
 
 ///////////////////////////////////////////////////////////////////////////////////////////////////
 
 bitflags! {
     #[derive(Default)]
+    #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
     pub struct Keys: u32 {
         const M1 = 1;
         const M2 = 2;
@@ -167,3 +1235,838 @@ bitflags! {
         const SMOKE = 16;
     }
 }
+
+impl Keys {
+    const NAMES: [(Keys, &'static str); 5] = [
+        (Keys::M1, "M1"),
+        (Keys::M2, "M2"),
+        (Keys::K1, "K1"),
+        (Keys::K2, "K2"),
+        (Keys::SMOKE, "SMOKE"),
+    ];
+
+    /// Same as `from_bits`, returning an error instead of `None` when unknown key bits are set
+    pub fn from_bits_checked(bits: Integer) -> Result<Keys, Error> {
+        Self::from_bits(bits).ok_or(Error::InvalidKeyBits { bits })
+    }
+}
+
+impl Display for Keys {
+    /// Pressed keys joined by `+` (ex. `M1+K1`), or `None` if no key is pressed
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        if self.is_empty() {
+            return f.write_str("None");
+        }
+
+        let names: Vec<&str> = Self::NAMES
+            .iter()
+            .filter(|(key, _)| self.contains(*key))
+            .map(|(_, name)| *name)
+            .collect();
+
+        f.write_str(&names.join("+"))
+    }
+}
+
+impl FromStr for Keys {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if s == "None" {
+            return Ok(Keys::empty());
+        }
+
+        let mut keys = Keys::empty();
+        for name in s.split('+') {
+            let (key, _) = Self::NAMES
+                .iter()
+                .find(|(_, key_name)| *key_name == name.trim())
+                .ok_or(Error::InvalidKeyName)?;
+            keys |= *key;
+        }
+
+        Ok(keys)
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////////////////////////
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::vec;
+
+    fn frames_data(frames: &[(Long, Float, Float)]) -> ReplayData {
+        let mut replay_data = ReplayData::new();
+        for &(w, x, y) in frames {
+            replay_data.frames.push(ReplayFrame {
+                w,
+                x,
+                y,
+                z: Keys::empty(),
+            });
+        }
+        replay_data
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn input_entropy() {
+        let mut frames = vec![(0, 256.0, -500.0), (-1, 256.0, -500.0)];
+        frames.extend([(16, 100.0, 100.0); 100]);
+        let uniform = frames_data(&frames);
+        assert!(uniform.input_entropy() < 1e-9);
+
+        let varied = frames_data(&[
+            (0, 256.0, -500.0),
+            (-1, 256.0, -500.0),
+            (15, 0.0, 0.0),
+            (16, 0.0, 0.0),
+            (17, 0.0, 0.0),
+            (18, 0.0, 0.0),
+        ]);
+        assert!((varied.input_entropy() - 1.0).abs() < 1e-9);
+        assert_eq!(ReplayData::new().input_entropy(), 0.0);
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn total_distance() {
+        let replay_data = frames_data(&[
+            (0, 256.0, -500.0),
+            (-1, 256.0, -500.0),
+            (16, 0.0, 0.0),
+            (16, 3.0, 4.0),
+            (16, 3.0, 4.0),
+            (16, 0.0, 0.0),
+        ]);
+
+        assert_eq!(replay_data.total_distance(), 10.0);
+        assert_eq!(ReplayData::new().total_distance(), 0.0);
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn spinner_segments() {
+        let mut frames = vec![(0, 256.0, -500.0)];
+        // 1 second of straight movement
+        for i in 0..62 {
+            frames.push((16, i as f32 * 4.0, 100.0));
+        }
+        // 2 seconds spinning around (256, 192) at 3 rotations per second
+        for i in 0..125 {
+            let angle = i as f32 * 16.0 * 3.0 * core::f32::consts::TAU / 1000.0;
+            frames.push((16, 256.0 + 60.0 * angle.cos(), 192.0 + 60.0 * angle.sin()));
+        }
+        // 1 second without movement
+        for _ in 0..62 {
+            frames.push((16, 256.0, 192.0));
+        }
+        let replay_data = frames_data(&frames);
+
+        let segments = replay_data.spinner_segments();
+
+        assert_eq!(segments.len(), 1);
+        let (start, end) = segments[0];
+        assert!((480..=1500).contains(&start));
+        assert!((2500..=3000).contains(&end));
+    }
+
+    #[cfg(feature = "ndarray")]
+    #[test]
+    fn to_ndarray() {
+        let replay_data = frames_data(&[(0, 256.0, -500.0), (10, 0.0, 0.0), (30, 100.0, 200.0)]);
+
+        let array = replay_data.to_ndarray();
+
+        assert_eq!(array.shape(), [replay_data.gameplay_frames().len(), 3]);
+        assert_eq!(array.row(1).to_vec(), vec![40.0, 100.0, 200.0]);
+    }
+
+    #[test]
+    fn hardrock_applied_flag() {
+        let mut replay_data = frames_data(&[(0, 256.0, -500.0), (10, 100.0, 100.0)]);
+        assert!(!replay_data.is_hardrock_applied());
+
+        replay_data.to_hardrock();
+        assert!(replay_data.is_hardrock_applied());
+        assert_eq!(replay_data.frames[1].y, 284.0);
+
+        replay_data.apply_mod_transform(Mods::HARDROCK, Gamemode::STD);
+        assert!(!replay_data.is_hardrock_applied());
+        assert_eq!(replay_data.frames[1].y, 100.0);
+
+        replay_data.apply_mod_transform(Mods::HARDROCK, Gamemode::MANIA);
+        assert!(!replay_data.is_hardrock_applied());
+    }
+
+    #[test]
+    fn gameplay_deltas() {
+        let mut replay_data =
+            frames_data(&[(0, 256.0, -500.0), (-1, 256.0, -500.0), (-20, 0.0, 0.0)]);
+        replay_data.frames.extend([
+            ReplayFrame {
+                w: 16,
+                ..ReplayFrame::default()
+            },
+            ReplayFrame {
+                w: 0,
+                ..ReplayFrame::default()
+            },
+            ReplayFrame {
+                w: ReplayFrame::SEED_FRAME_TIME,
+                ..ReplayFrame::default()
+            },
+        ]);
+
+        let deltas = replay_data.gameplay_deltas();
+
+        assert_eq!(deltas, vec![16, 0]);
+        assert!(!deltas.contains(&ReplayFrame::SEED_FRAME_TIME));
+    }
+
+    #[test]
+    fn aligned_frames() {
+        let replay_data = frames_data(&[(0, 256.0, -500.0), (10, 0.0, 0.0), (30, 100.0, 200.0)]);
+
+        assert_eq!(
+            replay_data.aligned_frames(0),
+            vec![(10, 0.0, 0.0), (40, 100.0, 200.0)]
+        );
+        assert_eq!(
+            replay_data.aligned_frames(-25),
+            vec![(-15, 0.0, 0.0), (15, 100.0, 200.0)]
+        );
+    }
+
+    #[test]
+    fn press_segments() {
+        let k1 = Keys::M1 | Keys::K1;
+        let k2 = Keys::M2 | Keys::K2;
+        let mut replay_data = frames_data(&[(0, 256.0, -500.0), (-1, 256.0, -500.0)]);
+        for (w, z) in [
+            (10, Keys::empty()),
+            (10, k1),
+            (10, k1),
+            (10, k1 | k2),
+            (10, k2),
+            (10, Keys::empty()),
+            (10, k1),
+        ] {
+            replay_data.frames.push(ReplayFrame {
+                w,
+                z,
+                ..ReplayFrame::default()
+            });
+        }
+
+        let segments = replay_data.press_segments();
+
+        assert_eq!(
+            segments,
+            vec![
+                PressSegment {
+                    start: 19,
+                    end: 39,
+                    keys: k1
+                },
+                PressSegment {
+                    start: 39,
+                    end: 49,
+                    keys: k1 | k2
+                },
+                PressSegment {
+                    start: 49,
+                    end: 59,
+                    keys: k2
+                },
+                PressSegment {
+                    start: 69,
+                    end: 69,
+                    keys: k1
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn estimated_stream_bpm() {
+        let mut replay_data = frames_data(&[(0, 256.0, -500.0), (-1, 256.0, -500.0)]);
+        // 32 alternating taps at 180 BPM (a tap every 83.33 milliseconds), then a few slower taps
+        let mut time = 0;
+        for i in 0..32 {
+            let tap_time = 1000 + i * 250 / 3;
+            let keys = if i % 2 == 0 {
+                Keys::M1 | Keys::K1
+            } else {
+                Keys::M2 | Keys::K2
+            };
+            for (frame_time, z) in [(tap_time, keys), (tap_time + 30, Keys::empty())] {
+                replay_data.frames.push(ReplayFrame {
+                    w: frame_time - time,
+                    z,
+                    ..ReplayFrame::default()
+                });
+                time = frame_time;
+            }
+        }
+        for _ in 0..4 {
+            replay_data.frames.push(ReplayFrame {
+                w: 400,
+                z: Keys::M1 | Keys::K1,
+                ..ReplayFrame::default()
+            });
+            replay_data.frames.push(ReplayFrame {
+                w: 30,
+                ..ReplayFrame::default()
+            });
+        }
+
+        let bpm = replay_data.estimated_stream_bpm().unwrap();
+
+        assert!((bpm - 180.0).abs() < 0.5);
+        assert_eq!(
+            frames_data(&[(0, 256.0, -500.0), (16, 0.0, 0.0)]).estimated_stream_bpm(),
+            None
+        );
+    }
+
+    #[test]
+    fn resample_frames() {
+        let mut replay_data = frames_data(&[
+            (0, 256.0, -500.0),
+            (-1, 256.0, -500.0),
+            (11, 0.0, 0.0),
+            (20, 100.0, 50.0),
+            (1980, 100.0, 50.0),
+        ]);
+        replay_data.frames[3].z = Keys::M1 | Keys::K1;
+
+        let samples = replay_data.resample_frames(100);
+
+        assert_eq!(samples.len(), 200);
+        assert_eq!(samples[0], (0.0, 0.0, 0));
+        assert_eq!(samples[1], (50.0, 25.0, 0));
+        assert_eq!(samples[2], (100.0, 50.0, 5));
+        assert_eq!(replay_data.resample_frames(60).len(), 120);
+        assert!(ReplayData::new().resample_frames(60).is_empty());
+    }
+
+    #[test]
+    fn decimate_for_render() {
+        let mut frames = vec![(0, 256.0, -500.0), (-1, 256.0, -500.0)];
+        for i in 0..500 {
+            let angle = i as f32 / 20.0;
+            frames.push((16, 256.0 + 100.0 * angle.cos(), 192.0 + 100.0 * angle.sin()));
+        }
+        let mut replay_data = frames_data(&frames);
+        let key_changes = [52, 200, 420, 421];
+        for (i, frame) in replay_data.frames.iter_mut().enumerate() {
+            if (52..200).contains(&i) || i == 420 {
+                frame.z = Keys::M1 | Keys::K1;
+            }
+        }
+        let gameplay_start = 2;
+
+        for max_points in [10, 50, 200, 1000] {
+            let points = replay_data.decimate_for_render(max_points);
+
+            assert!(points.len() <= max_points);
+            for i in key_changes {
+                let frame = replay_data.frames[i];
+                assert!(points.contains(&(frame.x, frame.y)));
+            }
+            let first = replay_data.frames[gameplay_start];
+            assert_eq!(points[0], (first.x, first.y));
+        }
+        assert_eq!(replay_data.decimate_for_render(1000).len(), 500);
+        assert_eq!(replay_data.decimate_for_render(3).len(), 3);
+        assert!(replay_data.decimate_for_render(0).is_empty());
+    }
+
+    #[test]
+    fn keys_at() {
+        let k1 = Keys::M1 | Keys::K1;
+        let replay_data = ReplayData::from_samples(&[
+            (100, 0.0, 0.0, 0),
+            (116, 0.0, 0.0, k1.bits()),
+            (132, 0.0, 0.0, (k1 | Keys::K2).bits()),
+            (148, 0.0, 0.0, 0),
+        ]);
+
+        assert_eq!(replay_data.keys_at(50), Keys::empty());
+        assert_eq!(replay_data.keys_at(100), Keys::empty());
+        assert_eq!(replay_data.keys_at(116), k1);
+        assert_eq!(replay_data.keys_at(131), k1);
+        assert_eq!(replay_data.keys_at(140), k1 | Keys::K2);
+        assert_eq!(replay_data.keys_at(1000), Keys::empty());
+    }
+
+    #[test]
+    fn coalesce_static_frames() {
+        let k1 = (Keys::M1 | Keys::K1).bits();
+        let mut replay_data = ReplayData::from_samples(&[
+            (-1, 256.0, -500.0, 0),
+            (10, 100.0, 100.0, 0),
+            (11, 100.0, 100.005, 0),
+            (12, 100.0, 100.0, 0),
+            (13, 100.0, 100.0, k1),
+            (14, 100.0, 100.0, k1),
+            (30, 120.0, 100.0, k1),
+            (31, 120.0, 100.0, 0),
+            (40, 120.0, 100.0, 0),
+            (50, 120.0, 100.0, 0),
+        ]);
+        replay_data.seed = Some(7);
+        replay_data.frames.push(ReplayFrame {
+            w: ReplayFrame::SEED_FRAME_TIME,
+            ..ReplayFrame::default()
+        });
+        let play_window = replay_data.play_window();
+        let key_presses = replay_data.key_presses();
+
+        replay_data.coalesce_static_frames();
+
+        assert_eq!(
+            replay_data.frames_absolute(),
+            vec![
+                (-1, 256.0, -500.0, 0),
+                (10, 100.0, 100.0, 0),
+                (13, 100.0, 100.0, k1),
+                (30, 120.0, 100.0, k1),
+                (31, 120.0, 100.0, 0),
+                (50, 120.0, 100.0, 0),
+            ]
+        );
+        assert_eq!(replay_data.play_window(), play_window);
+        assert_eq!(replay_data.key_presses(), key_presses);
+        assert_eq!(
+            replay_data.frames.last().unwrap().w,
+            ReplayFrame::SEED_FRAME_TIME
+        );
+    }
+
+    #[test]
+    fn pause_segments() {
+        let mut frames = vec![(0, 256.0, -500.0), (-1, 256.0, -500.0)];
+        frames.extend([(16, 0.0, 0.0); 10]);
+        frames.push((5000, 10.0, 10.0));
+        frames.extend([(16, 0.0, 0.0); 10]);
+        let mut replay_data = frames_data(&frames);
+        replay_data.frames.push(ReplayFrame {
+            w: ReplayFrame::SEED_FRAME_TIME,
+            ..ReplayFrame::default()
+        });
+
+        assert_eq!(replay_data.pause_segments(1000), vec![(159, 5159)]);
+        assert!(replay_data.pause_segments(5000).is_empty());
+    }
+
+    #[test]
+    fn samples_round_trip() {
+        let samples = [(-20, 0.0, 0.0, 0), (0, 10.5, 20.0, 5), (16, 30.0, 40.0, 10)];
+
+        let replay_data = ReplayData::from_samples(&samples);
+
+        assert_eq!(replay_data.frames[0].w, -20);
+        assert_eq!(replay_data.frames[1].w, 20);
+        assert_eq!(replay_data.frames[2].z, Keys::M2 | Keys::K2);
+        assert_eq!(replay_data.frames_absolute(), samples);
+    }
+
+    #[test]
+    fn approx_eq() {
+        let replay_data = frames_data(&[(0, 256.0, -500.0), (10, 100.25, 200.0)]);
+        let mut other = replay_data.clone();
+        other.frames[1].x += 0.0001;
+
+        assert!(replay_data.approx_eq(&other, 0.001));
+        assert!(!replay_data.approx_eq(&other, 0.0));
+
+        other.frames[1].w = 11;
+        assert!(!replay_data.approx_eq(&other, 0.001));
+    }
+
+    #[test]
+    fn centroid() {
+        let replay_data = frames_data(&[(0, 256.0, -500.0), (10, 0.0, 0.0), (30, 100.0, 200.0)]);
+
+        assert_eq!(replay_data.centroid(), (50.0, 100.0));
+        assert_eq!(replay_data.time_weighted_centroid(), (0.0, 0.0));
+        assert_eq!(ReplayData::new().centroid(), (0.0, 0.0));
+    }
+
+    #[test]
+    fn display_keys() {
+        assert_eq!(Keys::from_bits_truncate(5).to_string(), "M1+K1");
+        assert_eq!(Keys::SMOKE.to_string(), "SMOKE");
+        assert_eq!(Keys::empty().to_string(), "None");
+    }
+
+    #[test]
+    fn parse_keys() {
+        assert_eq!(Keys::from_str("M1+K1").unwrap().bits(), 5);
+        assert_eq!(Keys::from_str("None").unwrap(), Keys::empty());
+        assert!(Keys::from_str("M3").is_err());
+    }
+
+    #[test]
+    fn clicks_per_second() {
+        let mut replay_data = ReplayData::new();
+        // 20 taps, one every 100ms
+        for _ in 0..20 {
+            replay_data.frames.push(ReplayFrame {
+                w: 50,
+                z: Keys::M1 | Keys::K1,
+                ..ReplayFrame::default()
+            });
+            replay_data.frames.push(ReplayFrame {
+                w: 50,
+                ..ReplayFrame::default()
+            });
+        }
+
+        let cps = replay_data.clicks_per_second(ReplayData::DEFAULT_CPS_WINDOW_MS);
+
+        assert_eq!(cps.len(), 20);
+        assert_eq!(cps[0], (50, 1));
+        assert_eq!(cps[19], (1950, 10));
+    }
+
+    #[test]
+    fn clicks_per_second_invalid_window() {
+        let mut replay_data = ReplayData::new();
+        replay_data.frames.push(ReplayFrame {
+            w: 50,
+            z: Keys::M1 | Keys::K1,
+            ..ReplayFrame::default()
+        });
+
+        assert!(replay_data.clicks_per_second(0).is_empty());
+        assert!(replay_data.clicks_per_second(-1000).is_empty());
+        assert_eq!(replay_data.clicks_per_second(1), vec![(50, 1000)]);
+    }
+
+    #[test]
+    fn parse_frames_with_bom_and_crlf() {
+        let data =
+            "\u{feff}0|256|-500|0,\r\n-1|256|-500|0,\r\n16|224|73|5,\r\n-12345|0|0|19290764,";
+
+        let replay_data = ReplayData::from_str(data).unwrap();
+
+        assert_eq!(replay_data.frames.len(), 3);
+        assert_eq!(replay_data.frames[0].w, 0);
+        assert_eq!(replay_data.frames[2].z, Keys::M1 | Keys::K1);
+        assert_eq!(replay_data.seed, Some(19290764));
+    }
+
+    #[test]
+    fn parse_extra_frame_fields() {
+        let frame = "16|224.5|73|5|0";
+        let lenient = ParseOptions {
+            lenient_frames: true,
+            ..ParseOptions::default()
+        };
+
+        assert!(ReplayFrame::from_str(frame).is_err());
+        let frame = ReplayFrame::parse_with_options(frame, &lenient).unwrap();
+        assert_eq!(frame.w, 16);
+        assert_eq!(frame.x, 224.5);
+        assert_eq!(frame.y, 73.0);
+        assert_eq!(frame.z, Keys::M1 | Keys::K1);
+    }
+
+    #[test]
+    fn parse_non_finite_coordinates() {
+        for frame in [
+            "16|NaN|100|0",
+            "16|100|nan|0",
+            "16|inf|100|0",
+            "16|100|-inf|0",
+        ] {
+            assert!(matches!(
+                ReplayFrame::from_str(frame),
+                Err(Error::CantParseFrameValue)
+            ));
+        }
+        assert!(ReplayFrame::from_str("16|100.5|-20|0").is_ok());
+    }
+
+    #[test]
+    fn parse_unknown_key_bits() {
+        let frame = "16|224.5|73|37";
+        let strict = ParseOptions {
+            strict_keys: true,
+            ..ParseOptions::default()
+        };
+
+        assert_eq!(ReplayFrame::from_str(frame).unwrap().z, Keys::M1 | Keys::K1);
+        assert!(matches!(
+            ReplayFrame::parse_with_options(frame, &strict),
+            Err(Error::InvalidKeyBits { bits: 37 })
+        ));
+        assert!(ReplayFrame::parse_with_options("16|224.5|73|5", &strict).is_ok());
+    }
+
+    #[test]
+    fn active_key_count() {
+        let mut frame = ReplayFrame::new();
+
+        frame.z = Keys::from_bits_truncate(5);
+        assert_eq!(frame.active_key_count(), 2);
+        frame.z = Keys::from_bits_truncate(15);
+        assert_eq!(frame.active_key_count(), 4);
+        frame.z = Keys::from_bits_truncate(31);
+        assert_eq!(frame.active_key_count(), 4);
+    }
+
+    #[test]
+    fn set_frame_keys() {
+        let mut frame = ReplayFrame::from_str("16|224|73|1").unwrap();
+
+        frame.set_key(Keys::K1, true);
+        assert_eq!(String::from(&frame), "16|224|73|5");
+        frame.set_key(Keys::M1, false);
+        assert_eq!(String::from(&frame), "16|224|73|4");
+
+        let frame = frame.with_keys(Keys::M2 | Keys::K2);
+        assert_eq!(String::from(&frame), "16|224|73|10");
+    }
+
+    #[test]
+    fn malformed_seed_frame() {
+        let data = "0|256|-500|0,-1|256|-500|0,16|224|73|5,-12345|0|0|not-a-seed,";
+
+        let replay_data = ReplayData::from_str(data).unwrap();
+
+        assert_eq!(replay_data.seed, None);
+        assert_eq!(
+            replay_data.warnings,
+            [ParseWarning::MalformedSeed {
+                frame: "-12345|0|0|not-a-seed".to_string()
+            }]
+        );
+        assert_eq!(replay_data.frames.len(), 3);
+        assert_eq!(replay_data.gameplay_frames().len(), 1);
+
+        let options = ParseOptions {
+            keep_seed_frame: true,
+            ..ParseOptions::default()
+        };
+        let mut replay_data = ReplayData::parse_with_options(data, &options).unwrap();
+        assert_eq!(replay_data.frames.len(), 4);
+        assert_eq!(replay_data.gameplay_frames().len(), 1);
+        assert_eq!(String::from(&replay_data), data);
+
+        replay_data.seed = Some(42);
+        assert_eq!(
+            String::from(&replay_data),
+            "0|256|-500|0,-1|256|-500|0,16|224|73|5,-12345|0|0|42,"
+        );
+    }
+
+    #[test]
+    fn seed_frame_extracted() {
+        let replay_data = ReplayData::from_str("16|224|73|0,-12345|0|0|19290764,").unwrap();
+        assert!(replay_data.warnings.is_empty());
+
+        assert_eq!(replay_data.frames.len(), 1);
+        assert_eq!(replay_data.seed, Some(19290764));
+    }
+
+    #[test]
+    fn seed_frame_kept() {
+        let frames = "16|224|73|0,-12345|0|0|19290764,";
+        let options = ParseOptions {
+            keep_seed_frame: true,
+            ..ParseOptions::default()
+        };
+
+        let replay_data = ReplayData::parse_with_options(frames, &options).unwrap();
+
+        assert_eq!(replay_data.frames.len(), 2);
+        assert_eq!(replay_data.frames[1].w, ReplayFrame::SEED_FRAME_TIME);
+        assert_eq!(replay_data.seed, Some(19290764));
+        assert_eq!(String::from(&replay_data), frames);
+    }
+
+    #[test]
+    fn seed_frame_kept_verbatim() {
+        let frames = "16|224|73|0,-12345|0|0|019290764|0,";
+        let options = ParseOptions {
+            keep_seed_frame: true,
+            ..ParseOptions::default()
+        };
+
+        let mut replay_data = ReplayData::parse_with_options(frames, &options).unwrap();
+        assert_eq!(replay_data.seed, Some(19290764));
+        assert_eq!(String::from(&replay_data), frames);
+        assert_eq!(
+            String::from(&ReplayData::from_str(frames).unwrap()),
+            "16|224|73|0,-12345|0|0|19290764,"
+        );
+
+        replay_data.seed = Some(42);
+        assert_eq!(String::from(&replay_data), "16|224|73|0,-12345|0|0|42,");
+
+        replay_data.seed = None;
+        assert_eq!(String::from(&replay_data), "16|224|73|0,");
+    }
+
+    #[test]
+    fn gameplay_frames() {
+        let replay_data = ReplayData::from_str(
+            "0|256|-500|0,-1|256|-500|0,-651|224|73|0,16|224|74|1,-12345|0|0|19290764,",
+        )
+        .unwrap();
+        assert_eq!(replay_data.frames.len(), 4);
+        assert_eq!(replay_data.gameplay_frames().len(), 2);
+        assert_eq!(replay_data.gameplay_frames()[0].w, -651);
+
+        let mut replay_data = replay_data;
+        replay_data.frames.push(ReplayFrame {
+            w: ReplayFrame::SEED_FRAME_TIME,
+            ..ReplayFrame::default()
+        });
+        assert_eq!(replay_data.gameplay_frames().len(), 2);
+    }
+
+    #[test]
+    fn frames_checksum() {
+        let replay_data = frames_data(&[(16, 100.0, 50.0), (16, 300.0, 384.0)]);
+        let mut other = replay_data.clone();
+        assert_eq!(replay_data.frames_checksum(), other.frames_checksum());
+
+        other.frames[1].x = 301.0;
+        assert_ne!(replay_data.frames_checksum(), other.frames_checksum());
+    }
+
+    #[test]
+    fn estimated_poll_rate() {
+        let mut replay_data = frames_data(&[(16, 0.0, 0.0); 50]);
+        replay_data.frames[10].w = 0;
+        replay_data.frames[20].w = -3;
+        replay_data.frames[30].w = 17;
+
+        assert!((replay_data.estimated_poll_rate_hz() - 62.5).abs() < 0.001);
+        assert_eq!(ReplayData::new().estimated_poll_rate_hz(), 0.0);
+    }
+
+    #[test]
+    fn slice_time() {
+        let mut replay_data = frames_data(&[(0, 0.0, 0.0); 10]);
+        for frame in replay_data.frames.iter_mut() {
+            frame.w = 100;
+        }
+        replay_data.seed = Some(1234);
+
+        // frames at 100, 200, ..., 1000
+        let sliced = replay_data.slice_time(250, 600);
+
+        assert_eq!(sliced.frames.len(), 4);
+        assert_eq!(sliced.frames[0].w, 50);
+        assert_eq!(sliced.frames[1].w, 100);
+        assert_eq!(sliced.seed, Some(1234));
+    }
+
+    #[test]
+    fn std_hardrock_transform() {
+        let mut replay_data = frames_data(&[(16, 100.0, 50.0), (16, 300.0, 384.0)]);
+
+        replay_data.apply_mod_transform(Mods::HARDROCK, Gamemode::STD);
+
+        assert_eq!(replay_data.frames[0].x, 100.0);
+        assert_eq!(replay_data.frames[0].y, 334.0);
+        assert_eq!(replay_data.frames[1].y, 0.0);
+    }
+
+    #[test]
+    fn normalize_custom_playfield() {
+        let playfield = Playfield::new(640.0, 480.0);
+        let frame = ReplayFrame {
+            x: 320.0,
+            y: 120.0,
+            ..ReplayFrame::default()
+        };
+
+        assert_eq!(frame.normalize(&playfield), (0.5, 0.25));
+        assert_eq!(frame.normalize(&Playfield::default()), (0.625, 0.3125));
+    }
+
+    #[test]
+    fn hardrock_keeps_special_frames() {
+        let mut replay_data = ReplayData::from_str(
+            "0|256|-500|0,-1|256|-500|0,-651|224|73|0,16|224|74|1,-12345|0|0|19290764,",
+        )
+        .unwrap();
+        let gameplay_frame_count = replay_data.gameplay_frames().len();
+
+        replay_data.to_hardrock();
+
+        assert_eq!(replay_data.gameplay_frames().len(), gameplay_frame_count);
+        assert_eq!(replay_data.frames[0].y, -500.0);
+        assert_eq!(replay_data.frames[2].y, 311.0);
+    }
+
+    #[test]
+    fn mirror_keeps_seed() {
+        let frames = "0|256|-500|0,-1|256|-500|0,-651|224|73|0,16|224|74|1,-12345|0|0|19290764,";
+        let options = ParseOptions {
+            keep_seed_frame: true,
+            ..ParseOptions::default()
+        };
+        let mut replay_data = ReplayData::parse_with_options(frames, &options).unwrap();
+
+        replay_data.apply_mod_transform(Mods::MIRROR, Gamemode::STD);
+        assert_eq!(replay_data.frames[0].x, 256.0);
+        assert_eq!(replay_data.frames[2].x, 288.0);
+        assert_eq!(replay_data.frames[4].x, 0.0);
+
+        let replay_data = ReplayData::from_str(&String::from(&replay_data)).unwrap();
+        assert_eq!(replay_data.seed, Some(19290764));
+        assert_eq!(replay_data.gameplay_frames().len(), 2);
+    }
+
+    #[test]
+    fn hardrock_custom_playfield() {
+        let mut replay_data = frames_data(&[(16, 100.0, 40.0)]);
+
+        replay_data.to_hardrock_with_playfield(&Playfield::new(640.0, 480.0));
+
+        assert_eq!(replay_data.frames[0].y, 440.0);
+    }
+
+    #[test]
+    fn mania_mirror_transform() {
+        let mut replay_data = frames_data(&[(16, 1.0, 0.0), (16, 3.0, 0.0), (16, 0.0, 0.0)]);
+
+        replay_data.apply_mod_transform(Mods::MIRROR | Mods::KEY4, Gamemode::MANIA);
+
+        assert_eq!(replay_data.frames[0].x, 8.0);
+        assert_eq!(replay_data.frames[1].x, 12.0);
+        assert_eq!(replay_data.frames[2].x, 0.0);
+    }
+
+    #[test]
+    fn mania_mirror_keeps_special_frames() {
+        let frames = "0|256|-500|0,-1|256|-500|0,16|1|0|0,16|3|0|0,-12345|0|0|19290764,";
+        let options = ParseOptions {
+            keep_seed_frame: true,
+            ..ParseOptions::default()
+        };
+        let mut replay_data = ReplayData::parse_with_options(frames, &options).unwrap();
+
+        replay_data.apply_mod_transform(Mods::MIRROR, Gamemode::MANIA);
+
+        // the key count is guessed from the gameplay frames only
+        assert_eq!(replay_data.frames[2].x, 2.0);
+        assert_eq!(replay_data.frames[3].x, 3.0);
+        assert_eq!(replay_data.frames[0].x, 256.0);
+        assert_eq!(replay_data.frames[4].w, ReplayFrame::SEED_FRAME_TIME);
+        assert_eq!(replay_data.gameplay_frames().len(), 2);
+        assert_eq!(
+            String::from(&replay_data),
+            "0|256|-500|0,-1|256|-500|0,16|2|0|0,16|3|0|0,-12345|0|0|19290764,"
+        );
+    }
+}
@@ -0,0 +1,59 @@
+use super::*;
+
+/// Lightweight summary of a play without its replay data, for leaderboards
+/// keeping many scores in memory (see `ScoreSummary::from_replay`)
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ScoreSummary {
+    pub player_name: String,
+    /// Acronyms of the mods, as given by `Mods::acronyms`
+    pub mods: Vec<String>,
+    pub accuracy: Double,
+    pub grade: Grade,
+    pub greatest_combo: Short,
+    pub total_score: Integer,
+    pub play_date: NaiveDateTime,
+}
+
+impl ScoreSummary {
+    pub fn from_replay(replay: &Replay) -> Self {
+        Self {
+            player_name: replay.player_name.clone(),
+            mods: replay
+                .mods
+                .acronyms()
+                .iter()
+                .map(|acronym| acronym.to_string())
+                .collect(),
+            accuracy: replay.accuracy(),
+            grade: replay.grade(),
+            greatest_combo: replay.greatest_combo,
+            total_score: replay.total_score,
+            play_date: replay.play_date,
+        }
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////////////////////////
+
+#[cfg(all(test, feature = "std"))]
+mod tests {
+    use super::*;
+
+    use crate::utils::sample::sample_replay;
+
+    #[test]
+    fn summary_from_replay() {
+        let replay = sample_replay();
+
+        let summary = ScoreSummary::from_replay(&replay);
+
+        assert_eq!(summary.player_name, "Sailor SnoW");
+        assert_eq!(summary.mods, vec!["HD"]);
+        assert_eq!(summary.accuracy, replay.accuracy());
+        assert_eq!(summary.grade, Grade::SH);
+        assert_eq!(summary.greatest_combo, 852);
+        assert_eq!(summary.total_score, 13392443);
+        assert_eq!(summary.play_date, replay.play_date);
+    }
+}
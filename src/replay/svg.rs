@@ -0,0 +1,146 @@
+use super::replay_data::{Playfield, ReplayData, ReplayFrame};
+use alloc::format;
+use alloc::string::String;
+use alloc::vec;
+use alloc::vec::Vec;
+
+/// Options of the SVG rendering of a cursor path
+#[derive(Debug, Clone)]
+pub struct SvgOptions {
+    /// Size of the SVG, the cursor positions being in the osu! playfield coordinates by default
+    pub playfield: Playfield,
+    /// Color of the cursor path, escaped when written into the SVG
+    pub stroke: String,
+    /// Color of the parts of the path where a key is pressed,
+    /// the whole path is drawn with `stroke` if `None`
+    pub pressed_stroke: Option<String>,
+    pub stroke_width: f32,
+}
+
+impl Default for SvgOptions {
+    fn default() -> Self {
+        Self {
+            playfield: Playfield::default(),
+            stroke: String::from("#000000"),
+            pressed_stroke: None,
+            stroke_width: 1.0,
+        }
+    }
+}
+
+impl ReplayData {
+    /// Render the cursor path of the gameplay frames as an SVG made of `<polyline>` elements.
+    /// With `pressed_stroke` set, a new polyline is started each time the keys are pressed or released.
+    pub fn to_svg(&self, opts: SvgOptions) -> String {
+        let mut svg = format!(
+            r#"<svg xmlns="http://www.w3.org/2000/svg" viewBox="0 0 {w} {h}" width="{w}" height="{h}">"#,
+            w = opts.playfield.width,
+            h = opts.playfield.height
+        );
+
+        let mut segments: Vec<(bool, Vec<&ReplayFrame>)> = Vec::new();
+        for frame in self.gameplay_frames() {
+            let pressed = opts.pressed_stroke.is_some() && frame.active_key_count() > 0;
+
+            match segments.last_mut() {
+                Some((segment_pressed, frames)) if *segment_pressed == pressed => {
+                    frames.push(frame)
+                }
+                Some((_, frames)) => {
+                    // start from the end of the previous segment to keep the path continuous
+                    let last = *frames.last().unwrap();
+                    segments.push((pressed, vec![last, frame]))
+                }
+                None => segments.push((pressed, vec![frame])),
+            }
+        }
+
+        for (pressed, frames) in segments.iter() {
+            let stroke = match (pressed, &opts.pressed_stroke) {
+                (true, Some(pressed_stroke)) => pressed_stroke,
+                _ => &opts.stroke,
+            };
+            let points: Vec<String> = frames
+                .iter()
+                .map(|frame| format!("{},{}", frame.x, frame.y))
+                .collect();
+
+            svg.push_str(&format!(
+                r#"<polyline points="{}" fill="none" stroke="{}" stroke-width="{}"/>"#,
+                points.join(" "),
+                escape_xml(stroke),
+                opts.stroke_width
+            ));
+        }
+
+        svg.push_str("</svg>");
+        svg
+    }
+}
+
+/// Escape the characters of an XML attribute value
+fn escape_xml(s: &str) -> String {
+    let mut escaped = String::with_capacity(s.len());
+    for c in s.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&apos;"),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+////////////////////////////////////////////////////////////////////////////////////////////////////
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use core::str::FromStr;
+
+    const FRAMES: &str =
+        "0|256|-500|0,-1|256|-500|0,-651|224|73|0,16|230|80|5,16|240|90|5,16|250|100|0,";
+
+    #[test]
+    fn svg_polyline() {
+        let replay_data = ReplayData::from_str(FRAMES).unwrap();
+
+        let svg = replay_data.to_svg(SvgOptions::default());
+
+        assert!(svg.starts_with("<svg"));
+        assert_eq!(svg.matches("<polyline").count(), 1);
+        assert!(svg.contains(r#"points="224,73 230,80 240,90 250,100""#));
+    }
+
+    #[test]
+    fn svg_pressed_segments() {
+        let replay_data = ReplayData::from_str(FRAMES).unwrap();
+
+        let svg = replay_data.to_svg(SvgOptions {
+            pressed_stroke: Some(String::from("red")),
+            ..SvgOptions::default()
+        });
+
+        assert_eq!(svg.matches("<polyline").count(), 3);
+        assert_eq!(svg.matches(r#"stroke="red""#).count(), 1);
+        assert!(svg.contains(r#"points="224,73 230,80 240,90" fill="none" stroke="red""#));
+    }
+
+    #[test]
+    fn svg_escaped_stroke() {
+        let replay_data = ReplayData::from_str(FRAMES).unwrap();
+
+        let svg = replay_data.to_svg(SvgOptions {
+            stroke: String::from(r#"red" onload="alert(1)"#),
+            pressed_stroke: Some(String::from("<b>&")),
+            ..SvgOptions::default()
+        });
+
+        assert!(svg.contains(r#"stroke="red&quot; onload=&quot;alert(1)""#));
+        assert!(svg.contains(r#"stroke="&lt;b&gt;&amp;""#));
+        assert!(!svg.contains("onload=\""));
+    }
+}
@@ -1,6 +1,7 @@
 use crate::error::Error;
+use alloc::string::ToString;
+use alloc::vec::Vec;
 use bitflags::bitflags;
-use core::default::Default;
 
 // Data types
 pub type Byte = u8;
@@ -8,22 +9,20 @@ pub type Short = u16;
 pub type Integer = u32;
 pub type Long = i64;
 pub type Float = f32;
+pub type Double = f64;
 
 // Format types
-#[derive(Debug, PartialEq, Eq)]
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[allow(clippy::upper_case_acronyms)]
 pub enum Gamemode {
+    #[default]
     STD,
     TAIKO,
     CTB,
     MANIA,
 }
 
-impl Default for Gamemode {
-    fn default() -> Self {
-        Self::STD
-    }
-}
-
 impl From<&Gamemode> for u8 {
     fn from(gamemode: &Gamemode) -> Self {
         match gamemode {
@@ -49,8 +48,62 @@ impl TryFrom<Byte> for Gamemode {
     }
 }
 
+/// Grade (rank) of a play, as displayed on the score report
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[allow(clippy::upper_case_acronyms)]
+pub enum Grade {
+    /// Silver SS (SS with Hidden or Flashlight)
+    SSH,
+    /// Silver S (S with Hidden or Flashlight)
+    SH,
+    SS,
+    S,
+    A,
+    B,
+    C,
+    D,
+}
+
+impl Grade {
+    /// Name of the grade used internally by osu! (`XH`, `SH`, `X`, `S`, `A`...)
+    pub(crate) fn osu_name(&self) -> &'static str {
+        match self {
+            Grade::SSH => "XH",
+            Grade::SH => "SH",
+            Grade::SS => "X",
+            Grade::S => "S",
+            Grade::A => "A",
+            Grade::B => "B",
+            Grade::C => "C",
+            Grade::D => "D",
+        }
+    }
+}
+
+/// Hit counts of a play with gamemode-independent names (as named by osu!lazer).
+/// Judgements not existing in a gamemode are always 0.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Judgements {
+    /// Max 300s in mania
+    pub perfect: Short,
+    /// 300s in standard, mania and taiko, fruits in CTB
+    pub great: Short,
+    /// 200s in mania
+    pub good: Short,
+    /// 100s in standard and mania, 150s in taiko, large droplets in CTB
+    pub ok: Short,
+    /// 50s in standard and mania, small droplets in CTB
+    pub meh: Short,
+    pub miss: Short,
+    /// Missed small droplets in CTB, which don't count as misses but lower the accuracy
+    pub small_tick_miss: Short,
+}
+
 bitflags! {
     #[derive(Default)]
+    #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
     pub struct Mods: u32 {
         const NONE           = 0;
         const NO_FAIL         = 1;
@@ -69,15 +122,15 @@ bitflags! {
         const RELAX2         = 8192;    // Autopilot
         const PERFECT        = 16384; // Only set along with SuddenDeath. i.e: PF only gives 16416
         const KEY4           = 32768;
-        const KEY55           = 65536;
-        const KEY6           = 13107;
+        const KEY5           = 65536;
+        const KEY6           = 131072;
         const KEY7           = 262144;
         const KEY8           = 524288;
         const FADE_IN         = 1048576;
         const RANDOM         = 2097152;
         const CINEMA         = 4194304;
         const TARGET         = 8388608;
-        const KEY0          = 16777216;
+        const KEY9           = 16777216;
         const KEY_COOP        = 33554432;
         const KEY1           = 67108864;
         const KEY3           = 134217728;
@@ -87,6 +140,173 @@ bitflags! {
     }
 }
 
+impl Mods {
+    /// Former name of `KEY5`
+    #[deprecated(note = "renamed to `KEY5`")]
+    pub const KEY55: Mods = Mods::KEY5;
+    /// Former name of `KEY9`
+    #[deprecated(note = "renamed to `KEY9`")]
+    pub const KEY0: Mods = Mods::KEY9;
+
+    /// Acronym and display name of each mod, in the canonical order (the order of their bits)
+    const MODS: [(Mods, &'static str, &'static str); 31] = [
+        (Mods::NO_FAIL, "NF", "No Fail"),
+        (Mods::EASY, "EZ", "Easy"),
+        (Mods::TOUCH_DEVICE, "TD", "Touch Device"),
+        (Mods::HIDDEN, "HD", "Hidden"),
+        (Mods::HARDROCK, "HR", "Hard Rock"),
+        (Mods::SUDDEN_DEATH, "SD", "Sudden Death"),
+        (Mods::DOUBLETIME, "DT", "Double Time"),
+        (Mods::RELAX, "RX", "Relax"),
+        (Mods::HALFTIME, "HT", "Half Time"),
+        (Mods::NIGHTCORE, "NC", "Nightcore"),
+        (Mods::FLASHLIGHT, "FL", "Flashlight"),
+        (Mods::AUTOPLAY, "AT", "Autoplay"),
+        (Mods::SPUN_OUT, "SO", "Spun Out"),
+        (Mods::RELAX2, "AP", "Autopilot"),
+        (Mods::PERFECT, "PF", "Perfect"),
+        (Mods::KEY4, "4K", "4K"),
+        (Mods::KEY5, "5K", "5K"),
+        (Mods::KEY6, "6K", "6K"),
+        (Mods::KEY7, "7K", "7K"),
+        (Mods::KEY8, "8K", "8K"),
+        (Mods::FADE_IN, "FI", "Fade In"),
+        (Mods::RANDOM, "RD", "Random"),
+        (Mods::CINEMA, "CN", "Cinema"),
+        (Mods::TARGET, "TP", "Target Practice"),
+        (Mods::KEY9, "9K", "9K"),
+        (Mods::KEY_COOP, "CO", "Co-op"),
+        (Mods::KEY1, "1K", "1K"),
+        (Mods::KEY3, "3K", "3K"),
+        (Mods::KEY2, "2K", "2K"),
+        (Mods::SCORE_V2, "V2", "Score V2"),
+        (Mods::MIRROR, "MR", "Mirror"),
+    ];
+
+    /// Display name of each mod, built from `MODS` for `all_mods`
+    const NAMES: [(&'static str, Mods); 31] = {
+        let mut names = [("", Mods::NONE); 31];
+        let mut i = 0;
+        while i < Self::MODS.len() {
+            let (mod_flag, _, name) = Self::MODS[i];
+            names[i] = (name, mod_flag);
+            i += 1;
+        }
+        names
+    };
+
+    /// Every mod with its display name (ex. `("Hidden", Mods::HIDDEN)`), in the order of
+    /// their bits, to list the mods in a user interface
+    pub fn all_mods() -> &'static [(&'static str, Mods)] {
+        &Self::NAMES
+    }
+
+    /// Combine a list of mod acronyms (ex. `["HD", "HR"]`, case-insensitive) into mods.
+    /// Nightcore and Perfect also set the mods they depend on (DoubleTime and SuddenDeath).
+    pub fn from_acronyms(acronyms: &[&str]) -> Result<Mods, Error> {
+        let mut mods = Mods::NONE;
+
+        for acronym in acronyms {
+            let (mod_flag, _, _) = Self::MODS
+                .iter()
+                .find(|(_, mod_acronym, _)| mod_acronym.eq_ignore_ascii_case(acronym))
+                .ok_or_else(|| Error::UnknownModAcronym {
+                    acronym: acronym.to_string(),
+                })?;
+            mods |= *mod_flag;
+        }
+
+        if mods.contains(Mods::NIGHTCORE) {
+            mods |= Mods::DOUBLETIME;
+        }
+        if mods.contains(Mods::PERFECT) {
+            mods |= Mods::SUDDEN_DEATH;
+        }
+
+        Ok(mods)
+    }
+
+    /// Acronyms of the mods present, in the canonical order (ex. `["HD", "HR", "DT"]`).
+    /// The mods implied by Nightcore and Perfect are omitted, so NC gives `["NC"]` and not
+    /// `["DT", "NC"]`, like osu! displays them.
+    pub fn acronyms(&self) -> Vec<&'static str> {
+        let mut mods = *self;
+        if mods.contains(Mods::NIGHTCORE) {
+            mods.remove(Mods::DOUBLETIME);
+        }
+        if mods.contains(Mods::PERFECT) {
+            mods.remove(Mods::SUDDEN_DEATH);
+        }
+
+        Self::MODS
+            .iter()
+            .filter(|(mod_flag, _, _)| mods.contains(*mod_flag))
+            .map(|(_, acronym, _)| *acronym)
+            .collect()
+    }
+
+    /// Playback speed multiplier of the speed mods: 1.5 with DoubleTime (or Nightcore),
+    /// 0.75 with HalfTime, 1 otherwise
+    pub fn speed_multiplier(&self) -> f64 {
+        if self.contains(Mods::DOUBLETIME) {
+            1.5
+        } else if self.contains(Mods::HALFTIME) {
+            0.75
+        } else {
+            1.0
+        }
+    }
+
+    /// Number of mania keys set by the KEY mods (ex. 4 for KEY4), or `None` without KEY mod
+    pub fn mania_key_count(&self) -> Option<u32> {
+        const KEY_MODS: [(Mods, u32); 9] = [
+            (Mods::KEY1, 1),
+            (Mods::KEY2, 2),
+            (Mods::KEY3, 3),
+            (Mods::KEY4, 4),
+            (Mods::KEY5, 5),
+            (Mods::KEY6, 6),
+            (Mods::KEY7, 7),
+            (Mods::KEY8, 8),
+            (Mods::KEY9, 9),
+        ];
+
+        KEY_MODS
+            .iter()
+            .find(|(key_mod, _)| self.contains(*key_mod))
+            .map(|(_, key_count)| *key_count)
+    }
+
+    /// Gamemode implied by the mode-specific mods: osu!mania for the KEY mods, FadeIn, Random,
+    /// Co-op and Mirror, osu!standard for TouchDevice, SpunOut, Autopilot and Target Practice.
+    /// `None` without mode-specific mod, or when the mods imply different gamemodes.
+    pub fn implied_gamemode(&self) -> Option<Gamemode> {
+        const MANIA_MODS: Mods = Mods::KEY1
+            .union(Mods::KEY2)
+            .union(Mods::KEY3)
+            .union(Mods::KEY4)
+            .union(Mods::KEY5)
+            .union(Mods::KEY6)
+            .union(Mods::KEY7)
+            .union(Mods::KEY8)
+            .union(Mods::KEY9)
+            .union(Mods::FADE_IN)
+            .union(Mods::RANDOM)
+            .union(Mods::KEY_COOP)
+            .union(Mods::MIRROR);
+        const STD_MODS: Mods = Mods::TOUCH_DEVICE
+            .union(Mods::SPUN_OUT)
+            .union(Mods::RELAX2)
+            .union(Mods::TARGET);
+
+        match (self.intersects(MANIA_MODS), self.intersects(STD_MODS)) {
+            (true, false) => Some(Gamemode::MANIA),
+            (false, true) => Some(Gamemode::STD),
+            _ => None,
+        }
+    }
+}
+
 impl From<Integer> for Mods {
     fn from(value: Integer) -> Self {
         Self::from_bits_truncate(value)
@@ -98,3 +318,142 @@ impl From<Mods> for Integer {
         mods.bits()
     }
 }
+
+////////////////////////////////////////////////////////////////////////////////////////////////////
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::vec;
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn gamemode_map_key() {
+        use std::collections::HashMap;
+
+        let gamemodes = [
+            Gamemode::STD,
+            Gamemode::TAIKO,
+            Gamemode::CTB,
+            Gamemode::MANIA,
+        ];
+        let mut counts: HashMap<Gamemode, u32> = HashMap::new();
+        for gamemode in gamemodes {
+            *counts.entry(gamemode).or_default() += 1;
+        }
+        *counts.entry(gamemodes[0]).or_default() += 1;
+
+        assert_eq!(counts.len(), 4);
+        assert_eq!(counts[&Gamemode::STD], 2);
+        assert_eq!(counts[&Gamemode::MANIA], 1);
+    }
+
+    #[test]
+    fn mods_from_acronyms() {
+        assert_eq!(
+            Mods::from_acronyms(&["HD", "hr"]).unwrap(),
+            Mods::HIDDEN | Mods::HARDROCK
+        );
+        assert_eq!(
+            Mods::from_acronyms(&["NC"]).unwrap(),
+            Mods::NIGHTCORE | Mods::DOUBLETIME
+        );
+        assert_eq!(
+            Mods::from_acronyms(&["PF"]).unwrap(),
+            Mods::PERFECT | Mods::SUDDEN_DEATH
+        );
+        assert_eq!(Mods::from_acronyms(&[]).unwrap(), Mods::NONE);
+    }
+
+    #[test]
+    fn mods_acronyms() {
+        let mods = Mods::HIDDEN | Mods::HARDROCK | Mods::DOUBLETIME;
+        assert_eq!(mods.acronyms(), vec!["HD", "HR", "DT"]);
+
+        let mods = Mods::from_acronyms(&["NC", "PF"]).unwrap();
+        assert_eq!(mods.acronyms(), vec!["NC", "PF"]);
+        assert!(Mods::NONE.acronyms().is_empty());
+    }
+
+    #[test]
+    fn speed_multiplier() {
+        assert_eq!(Mods::NONE.speed_multiplier(), 1.0);
+        assert_eq!(
+            Mods::from_acronyms(&["NC"]).unwrap().speed_multiplier(),
+            1.5
+        );
+        assert_eq!((Mods::HALFTIME | Mods::HIDDEN).speed_multiplier(), 0.75);
+    }
+
+    #[test]
+    fn all_mods() {
+        let all_mods = Mods::all_mods();
+
+        assert_eq!(all_mods.len(), 31);
+        assert!(all_mods.contains(&("Hidden", Mods::HIDDEN)));
+        assert!(all_mods.contains(&("Hard Rock", Mods::HARDROCK)));
+        assert_eq!(Mods::HIDDEN.bits(), 8);
+        assert_eq!(Mods::HARDROCK.bits(), 16);
+        assert!(all_mods.contains(&("6K", Mods::from_bits_truncate(131072))));
+        let combined = all_mods
+            .iter()
+            .fold(Mods::NONE, |mods, (_, mod_flag)| mods | *mod_flag);
+        assert_eq!(combined, Mods::all());
+    }
+
+    #[test]
+    #[allow(deprecated)]
+    fn deprecated_key_aliases() {
+        assert_eq!(Mods::KEY55, Mods::KEY5);
+        assert_eq!(Mods::KEY0, Mods::KEY9);
+    }
+
+    #[test]
+    fn mania_key_count() {
+        let key_mods = [
+            Mods::KEY1,
+            Mods::KEY2,
+            Mods::KEY3,
+            Mods::KEY4,
+            Mods::KEY5,
+            Mods::KEY6,
+            Mods::KEY7,
+            Mods::KEY8,
+            Mods::KEY9,
+        ];
+        for (i, key_mod) in key_mods.iter().enumerate() {
+            assert_eq!(
+                (*key_mod | Mods::HIDDEN).mania_key_count(),
+                Some(i as u32 + 1)
+            );
+        }
+
+        assert_eq!(Mods::from_acronyms(&["6K"]).unwrap().bits(), 131072);
+        assert_eq!(Mods::HIDDEN.mania_key_count(), None);
+        assert_eq!(Mods::NONE.mania_key_count(), None);
+    }
+
+    #[test]
+    fn implied_gamemode() {
+        assert_eq!(
+            (Mods::KEY4 | Mods::FADE_IN).implied_gamemode(),
+            Some(Gamemode::MANIA)
+        );
+        assert_eq!(
+            (Mods::HIDDEN | Mods::RELAX2).implied_gamemode(),
+            Some(Gamemode::STD)
+        );
+        assert_eq!((Mods::KEY7 | Mods::SPUN_OUT).implied_gamemode(), None);
+        assert_eq!((Mods::HIDDEN | Mods::HARDROCK).implied_gamemode(), None);
+    }
+
+    #[test]
+    fn unknown_mod_acronym() {
+        let result = Mods::from_acronyms(&["HD", "XX"]);
+
+        assert!(matches!(
+            result,
+            Err(Error::UnknownModAcronym { acronym }) if acronym == "XX"
+        ));
+    }
+}
@@ -1,9 +1,9 @@
 use crate::types::Long;
-use chrono::NaiveDateTime;
+use chrono::{DateTime, NaiveDateTime};
 
 pub mod read {
     use crate::error::Error;
-    use crate::types::{Byte, Integer, Long, Short};
+    use crate::types::{Byte, Double, Integer, Long, Short};
     use byteorder::{ByteOrder, LittleEndian};
     use std::io::Read;
 
@@ -33,66 +33,308 @@ pub mod read {
         Ok(LittleEndian::read_i64(&x))
     }
 
+    pub fn read_double<R: Read>(buf: &mut R) -> ReadResult<Double> {
+        let mut x = [0, 0, 0, 0, 0, 0, 0, 0];
+        buf.read_exact(&mut x)
+            .map_err(|_| Error::ReadBufferingError)?;
+        Ok(LittleEndian::read_f64(&x))
+    }
+
+    /// Read an unsigned LEB128 value (7 bits per byte, the high bit set on all bytes but the last)
+    pub fn read_uleb128<R: Read>(buf: &mut R) -> ReadResult<u64> {
+        let mut value = 0;
+        let mut shift = 0;
+        loop {
+            let mut x = [0];
+            buf.read_exact(&mut x)
+                .map_err(|_| Error::ReadBufferingError)?;
+            if shift >= u64::BITS {
+                return Err(Error::CantReadString);
+            }
+            value |= ((x[0] & 0x7F) as u64) << shift;
+            if x[0] & 0x80 == 0 {
+                return Ok(value);
+            }
+            shift += 7;
+        }
+    }
+
     pub fn read_string<R: Read>(buf: &mut R) -> ReadResult<Option<String>> {
+        match read_string_bytes(buf)? {
+            Some(x) => Ok(Some(
+                String::from_utf8(x).map_err(|_| Error::CantReadString)?,
+            )),
+            None => Ok(None),
+        }
+    }
+
+    /// Read the raw bytes of a string, to decode them with another encoding than UTF-8
+    pub fn read_string_bytes<R: Read>(buf: &mut R) -> ReadResult<Option<Vec<u8>>> {
         match read_byte(buf)? {
             0x0b => {
-                let string_size = read_byte(buf)?;
-                if string_size == 0 {
-                    return Ok(Some(String::from("")));
-                }
-                let mut x = vec![0u8; string_size as usize];
-                buf.read_exact(&mut x)
+                let string_size = read_uleb128(buf)?;
+                // the length is untrusted: read what is there instead of allocating it upfront
+                let mut x = Vec::new();
+                buf.take(string_size)
+                    .read_to_end(&mut x)
                     .map_err(|_| Error::ReadBufferingError)?;
-                Ok(Some(
-                    String::from_utf8(x).map_err(|_| Error::CantReadString)?,
-                ))
+                if x.len() as u64 != string_size {
+                    return Err(Error::CantReadString);
+                }
+                Ok(Some(x))
             }
-            0x00 => {
-                return Ok(None);
+            0x00 => Ok(None),
+            _ => Err(Error::UnexpectedStringValue),
+        }
+    }
+}
+
+pub mod write {
+    use crate::types::{Byte, Double, Integer, Long, Short};
+    use byteorder::{ByteOrder, LittleEndian};
+
+    pub fn write_byte(value: Byte, buf: &mut Vec<u8>) {
+        buf.push(value);
+    }
+
+    pub fn write_short(value: Short, buf: &mut Vec<u8>) {
+        let mut x = [0; 2];
+        LittleEndian::write_u16(&mut x, value);
+        buf.extend_from_slice(&x);
+    }
+
+    pub fn write_integer(value: Integer, buf: &mut Vec<u8>) {
+        let mut x = [0; 4];
+        LittleEndian::write_u32(&mut x, value);
+        buf.extend_from_slice(&x);
+    }
+
+    pub fn write_long(value: Long, buf: &mut Vec<u8>) {
+        let mut x = [0; 8];
+        LittleEndian::write_i64(&mut x, value);
+        buf.extend_from_slice(&x);
+    }
+
+    pub fn write_double(value: Double, buf: &mut Vec<u8>) {
+        let mut x = [0; 8];
+        LittleEndian::write_f64(&mut x, value);
+        buf.extend_from_slice(&x);
+    }
+
+    /// Write an unsigned LEB128 value (7 bits per byte, the high bit set on all bytes but the last)
+    pub fn write_uleb128(mut value: u64, buf: &mut Vec<u8>) {
+        loop {
+            let byte = (value & 0x7F) as u8;
+            value >>= 7;
+            if value == 0 {
+                buf.push(byte);
+                return;
             }
-            _ => return Err(Error::UnexpectedStringValue),
+            buf.push(byte | 0x80);
         }
     }
 
+    /// Write an osu! string: `0x0B`, its length in bytes as ULEB128 and its UTF-8 bytes,
+    /// or `0x00` for no string
     pub fn write_string(str: &Option<&str>, buf: &mut Vec<u8>) {
         match str {
             Some(str) => {
-                let str_len = str.len() as u8;
-                buf.append(&mut 0x0Bu8.to_le_bytes().to_vec());
-                buf.append(&mut str_len.to_le_bytes().to_vec());
-                buf.append(&mut str.as_bytes().to_vec());
+                write_byte(0x0B, buf);
+                write_uleb128(str.len() as u64, buf);
+                buf.extend_from_slice(str.as_bytes());
             }
-            None => buf.append(&mut 0u8.to_le_bytes().to_vec()),
+            None => write_byte(0x00, buf),
         }
     }
 }
 
 pub mod lzma {
+    #[cfg(target_arch = "wasm32")]
+    pub use self::lzma_rs_backend::*;
+    #[cfg(not(target_arch = "wasm32"))]
+    pub use self::xz2_backend::*;
     use crate::error::Error;
-    use xz2::stream::{Action, LzmaOptions, Stream};
 
-    pub fn decompress_replay_data(compressed_data: &Vec<u8>) -> Result<Vec<u8>, Error> {
-        let buffer = compressed_data.as_slice();
-        let mut s = Vec::with_capacity(u32::MAX as usize);
+    /// Decompress the replay data, using LZMA like osu! does.
+    /// With the `gzip` feature, gzip data is detected from its magic bytes
+    /// and zlib/raw DEFLATE data is tried when the LZMA decoding fails.
+    pub fn decompress_replay_data(compressed_data: &[u8]) -> Result<Vec<u8>, Error> {
+        #[cfg(feature = "gzip")]
+        if compressed_data.starts_with(&super::deflate::GZIP_MAGIC) {
+            return super::deflate::decompress_gzip(compressed_data);
+        }
 
-        let mut lzma_decoder = Stream::new_lzma_decoder(u32::MAX as u64).unwrap();
+        match decompress_lzma(compressed_data) {
+            Ok(data) => Ok(data),
+            #[cfg(feature = "gzip")]
+            Err(_) => super::deflate::decompress_deflate(compressed_data),
+            #[cfg(not(feature = "gzip"))]
+            Err(e) => Err(e),
+        }
+    }
 
-        lzma_decoder
-            .process_vec(buffer, &mut s, Action::Finish)
-            .unwrap();
-        Ok(s)
+    /// Decompress LZMA data made of exactly one stream, failing when the stream is truncated
+    /// or followed by other bytes
+    pub fn decompress_lzma_exact(compressed_data: &[u8]) -> Result<Vec<u8>, Error> {
+        match decompress_lzma_stream(compressed_data)? {
+            (data, read) if read == compressed_data.len() => Ok(data),
+            _ => Err(Error::ReplayDataDecompressError),
+        }
     }
 
-    pub fn compress_replay_data(uncompressed_data: Vec<u8>) -> Result<Vec<u8>, Error> {
-        let mut lzma_encoder =
-            Stream::new_lzma_encoder(&LzmaOptions::new_preset(6).unwrap()).unwrap();
-        let mut buffer = Vec::with_capacity(uncompressed_data.len());
+    /// LZMA (de)compression with the C liblzma
+    #[cfg(not(target_arch = "wasm32"))]
+    mod xz2_backend {
+        use crate::error::Error;
+        use std::io::Read;
+        use xz2::read::XzDecoder;
+        use xz2::stream::{Action, LzmaOptions, Status, Stream};
+
+        pub(super) fn decompress_lzma(compressed_data: &[u8]) -> Result<Vec<u8>, Error> {
+            let mut s = Vec::with_capacity(compressed_data.len() * 4);
 
-        lzma_encoder
-            .process_vec(&uncompressed_data, &mut buffer, Action::Finish)
-            .unwrap();
+            let mut lzma_decoder = Stream::new_lzma_decoder(u32::MAX as u64)
+                .map_err(|_| Error::ReplayDataDecompressError)?;
 
-        Ok(buffer)
+            loop {
+                // `process_vec` only writes into the spare capacity
+                if s.len() == s.capacity() {
+                    s.reserve(s.capacity().max(4096));
+                }
+                let read = lzma_decoder.total_in() as usize;
+
+                let status = lzma_decoder
+                    .process_vec(&compressed_data[read..], &mut s, Action::Finish)
+                    .map_err(|_| Error::ReplayDataDecompressError)?;
+                if status == Status::StreamEnd || s.len() < s.capacity() {
+                    return Ok(s);
+                }
+            }
+        }
+
+        /// Decompress LZMA data up to the end marker of the stream, ignoring the bytes following it.
+        /// Returns the decompressed data and the number of compressed bytes read.
+        pub fn decompress_lzma_stream(compressed_data: &[u8]) -> Result<(Vec<u8>, usize), Error> {
+            let mut s = Vec::with_capacity(compressed_data.len() * 4);
+
+            let mut lzma_decoder = Stream::new_lzma_decoder(u32::MAX as u64)
+                .map_err(|_| Error::ReplayDataDecompressError)?;
+
+            loop {
+                if s.len() == s.capacity() {
+                    s.reserve(s.capacity().max(4096));
+                }
+                let read = lzma_decoder.total_in() as usize;
+                let written = s.len();
+
+                let status = lzma_decoder
+                    .process_vec(&compressed_data[read..], &mut s, Action::Run)
+                    .map_err(|_| Error::ReplayDataDecompressError)?;
+                if status == Status::StreamEnd {
+                    break;
+                }
+                if lzma_decoder.total_in() as usize == read && s.len() == written {
+                    // no more progress without the end of the stream
+                    return Err(Error::ReplayDataDecompressError);
+                }
+            }
+
+            Ok((s, lzma_decoder.total_in() as usize))
+        }
+
+        /// Reader decompressing the LZMA replay data progressively
+        pub fn replay_data_decoder<R: Read>(compressed_data: R) -> Result<impl Read, Error> {
+            let lzma_decoder = Stream::new_lzma_decoder(u32::MAX as u64)
+                .map_err(|_| Error::ReplayDataDecompressError)?;
+
+            Ok(XzDecoder::new_stream(compressed_data, lzma_decoder))
+        }
+
+        pub fn compress_replay_data(uncompressed_data: Vec<u8>) -> Result<Vec<u8>, Error> {
+            let mut lzma_encoder =
+                Stream::new_lzma_encoder(&LzmaOptions::new_preset(6).unwrap()).unwrap();
+            let mut buffer = Vec::with_capacity(uncompressed_data.len());
+
+            lzma_encoder
+                .process_vec(&uncompressed_data, &mut buffer, Action::Finish)
+                .unwrap();
+
+            Ok(buffer)
+        }
+    }
+
+    /// LZMA (de)compression in pure Rust, for the wasm targets where the C liblzma doesn't build.
+    /// Unlike liblzma, truncated LZMA data fails to decompress instead of giving partial data.
+    #[cfg(target_arch = "wasm32")]
+    mod lzma_rs_backend {
+        use crate::error::Error;
+        use std::io::{Cursor, Read};
+
+        pub(super) fn decompress_lzma(compressed_data: &[u8]) -> Result<Vec<u8>, Error> {
+            decompress_lzma_stream(compressed_data).map(|(data, _)| data)
+        }
+
+        /// Decompress LZMA data up to the end of the stream, ignoring the bytes following it.
+        /// Returns the decompressed data and the number of compressed bytes read.
+        pub fn decompress_lzma_stream(compressed_data: &[u8]) -> Result<(Vec<u8>, usize), Error> {
+            let mut input = compressed_data;
+            let mut s = Vec::with_capacity(compressed_data.len() * 4);
+
+            lzma_rs::lzma_decompress(&mut input, &mut s)
+                .map_err(|_| Error::ReplayDataDecompressError)?;
+
+            Ok((s, compressed_data.len() - input.len()))
+        }
+
+        /// Reader over the decompressed replay data, decompressed all at once
+        pub fn replay_data_decoder<R: Read>(mut compressed_data: R) -> Result<impl Read, Error> {
+            let mut compressed = Vec::new();
+            compressed_data
+                .read_to_end(&mut compressed)
+                .map_err(|_| Error::ReplayDataDecompressError)?;
+
+            Ok(Cursor::new(decompress_lzma(&compressed)?))
+        }
+
+        pub fn compress_replay_data(uncompressed_data: Vec<u8>) -> Result<Vec<u8>, Error> {
+            let mut buffer = Vec::with_capacity(uncompressed_data.len());
+
+            lzma_rs::lzma_compress(&mut uncompressed_data.as_slice(), &mut buffer)
+                .map_err(|_| Error::NewLzmaEncoderError)?;
+
+            Ok(buffer)
+        }
+    }
+}
+
+#[cfg(feature = "gzip")]
+pub mod deflate {
+    use crate::error::Error;
+    use flate2::read::{DeflateDecoder, GzDecoder, ZlibDecoder};
+    use std::io::Read;
+
+    pub const GZIP_MAGIC: [u8; 2] = [0x1f, 0x8b];
+
+    pub fn decompress_gzip(compressed_data: &[u8]) -> Result<Vec<u8>, Error> {
+        read_all(GzDecoder::new(compressed_data))
+    }
+
+    /// Decompress zlib data (detected from its header) or raw DEFLATE data
+    pub fn decompress_deflate(compressed_data: &[u8]) -> Result<Vec<u8>, Error> {
+        match compressed_data {
+            [0x78, flags, ..] if (0x7800 | *flags as u16).is_multiple_of(31) => {
+                read_all(ZlibDecoder::new(compressed_data))
+            }
+            _ => read_all(DeflateDecoder::new(compressed_data)),
+        }
+    }
+
+    fn read_all<R: Read>(mut decoder: R) -> Result<Vec<u8>, Error> {
+        let mut s = Vec::new();
+        decoder
+            .read_to_end(&mut s)
+            .map_err(|_| Error::ReplayDataDecompressError)?;
+        Ok(s)
     }
 }
 
@@ -102,7 +344,7 @@ pub mod file {
 
     pub fn ensure_replay_file(path: &Path) -> Result<(), Error> {
         match path.extension() {
-            Some(extension) if extension == "osr" => Ok(()),
+            Some(extension) if extension.eq_ignore_ascii_case("osr") => Ok(()),
             Some(_) => Err(Error::NotAReplayFile {
                 file: path.to_string_lossy().to_string(),
             }),
@@ -114,13 +356,155 @@ pub mod file {
 }
 
 pub fn ticks_to_datetime(t_ticks: Long) -> NaiveDateTime {
-    NaiveDateTime::from_timestamp(((t_ticks / 10000000) - 62135596800).try_into().unwrap(), 0)
+    DateTime::from_timestamp((t_ticks / 10000000) - 62135596800, 0)
+        .unwrap_or_default()
+        .naive_utc()
 }
 
 pub fn datetime_to_ticks(datetime: NaiveDateTime) -> Long {
-    let unix = datetime.timestamp();
-    let t_ticks = ((unix + 62135596800) * 10000000) as Long;
-    t_ticks
+    let unix = datetime.and_utc().timestamp();
+    (unix + 62135596800) * 10000000
+}
+
+/// Sample replay shared by the tests
+#[cfg(test)]
+pub mod sample {
+    use crate::replay::Replay;
+
+    pub const TEST_REPLAY_FILE: &str = "./assets/examples/replay-test.osr";
+
+    pub fn sample_replay() -> Replay {
+        Replay::open(TEST_REPLAY_FILE).unwrap()
+    }
+}
+
+#[cfg(test)]
+#[test]
+fn replay_file_extension_case_insensitive() {
+    use std::path::Path;
+
+    assert!(file::ensure_replay_file(Path::new("replay.osr")).is_ok());
+    assert!(file::ensure_replay_file(Path::new("replay.OSR")).is_ok());
+    assert!(file::ensure_replay_file(Path::new("replay.Osr")).is_ok());
+    assert!(file::ensure_replay_file(Path::new("replay.osu")).is_err());
+}
+
+#[cfg(test)]
+#[test]
+fn write_values() {
+    use write::*;
+
+    let mut buf = Vec::new();
+    write_byte(0x2A, &mut buf);
+    assert_eq!(buf, [0x2A]);
+
+    let mut buf = Vec::new();
+    write_short(0x1234, &mut buf);
+    assert_eq!(buf, [0x34, 0x12]);
+
+    let mut buf = Vec::new();
+    write_integer(20210520, &mut buf);
+    assert_eq!(buf, [0x58, 0x63, 0x34, 0x01]);
+
+    let mut buf = Vec::new();
+    write_long(-2, &mut buf);
+    assert_eq!(buf, [0xFE, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF]);
+
+    let mut buf = Vec::new();
+    write_double(1.0, &mut buf);
+    assert_eq!(buf, [0, 0, 0, 0, 0, 0, 0xF0, 0x3F]);
+
+    let mut buf = Vec::new();
+    write_string(&Some("osu"), &mut buf);
+    write_string(&None, &mut buf);
+    assert_eq!(buf, [0x0B, 3, b'o', b's', b'u', 0x00]);
+}
+
+#[cfg(test)]
+#[test]
+fn write_read_round_trip() {
+    use std::io::Cursor;
+
+    let mut buf = Vec::new();
+    write::write_short(852, &mut buf);
+    write::write_integer(13392443, &mut buf);
+    write::write_long(3760034870, &mut buf);
+    write::write_double(0.5, &mut buf);
+
+    let cursor = &mut Cursor::new(buf);
+    assert_eq!(read::read_short(cursor).unwrap(), 852);
+    assert_eq!(read::read_integer(cursor).unwrap(), 13392443);
+    assert_eq!(read::read_long(cursor).unwrap(), 3760034870);
+    assert_eq!(read::read_double(cursor).unwrap(), 0.5);
+}
+
+#[cfg(test)]
+#[test]
+fn uleb128_round_trip() {
+    use std::io::Cursor;
+
+    let mut buf = Vec::new();
+    write::write_uleb128(127, &mut buf);
+    assert_eq!(buf, [0x7F]);
+
+    let mut buf = Vec::new();
+    write::write_uleb128(624485, &mut buf);
+    assert_eq!(buf, [0xE5, 0x8E, 0x26]);
+
+    let long_string = "x".repeat(300);
+    let mut buf = Vec::new();
+    write::write_string(&Some(&long_string), &mut buf);
+    assert_eq!(&buf[..3], [0x0B, 0xAC, 0x02]);
+
+    let cursor = &mut Cursor::new(buf);
+    assert_eq!(read::read_string(cursor).unwrap(), Some(long_string));
+}
+
+#[cfg(test)]
+#[test]
+fn read_string_oversized_length() {
+    use std::io::Cursor;
+
+    let mut buf = vec![0x0B];
+    write::write_uleb128(i64::MAX as u64, &mut buf);
+    buf.extend_from_slice(b"osu");
+
+    let cursor = &mut Cursor::new(buf);
+    assert!(matches!(
+        read::read_string(cursor),
+        Err(crate::error::Error::CantReadString)
+    ));
+}
+
+#[cfg(test)]
+#[test]
+fn decompress_lzma_exact_length() {
+    let bytes = std::fs::read(sample::TEST_REPLAY_FILE).unwrap();
+    // the compressed length follows the play date, 119 bytes into the sample replay
+    let compressed_length = u32::from_le_bytes(bytes[119..123].try_into().unwrap()) as usize;
+    let compressed = &bytes[123..123 + compressed_length];
+
+    assert_eq!(
+        lzma::decompress_lzma_exact(compressed).unwrap(),
+        lzma::decompress_replay_data(compressed).unwrap()
+    );
+    assert!(lzma::decompress_lzma_exact(&compressed[..compressed_length - 5]).is_err());
+    assert!(lzma::decompress_lzma_exact(&bytes[123..123 + compressed_length + 5]).is_err());
+}
+
+#[cfg(all(test, feature = "gzip"))]
+#[test]
+fn decompress_gzip_replay_data() {
+    use flate2::write::GzEncoder;
+    use flate2::Compression;
+    use std::io::Write;
+
+    let frames = b"0|256|-500|0,-1|256|-500|0,16|224|73|5,-12345|0|0|19290764,";
+    let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+    encoder.write_all(frames).unwrap();
+    let compressed = encoder.finish().unwrap();
+
+    assert_eq!(lzma::decompress_replay_data(&compressed).unwrap(), frames);
 }
 
 #[cfg(test)]
@@ -0,0 +1,20 @@
+use crate::replay::Replay;
+use alloc::string::ToString;
+use wasm_bindgen::prelude::*;
+
+/// Parse the bytes of a replay file (.osr) into a JS object mirroring the `Replay` structure.
+///
+/// ```js
+/// import init, { parseReplay } from "osu-replay-parser";
+///
+/// await init();
+/// const bytes = new Uint8Array(await file.arrayBuffer());
+/// const replay = parseReplay(bytes);
+/// console.log(replay.player_name, replay.replay_data.frames.length);
+/// ```
+#[wasm_bindgen(js_name = parseReplay)]
+pub fn parse_replay(bytes: &[u8]) -> Result<JsValue, JsValue> {
+    let replay = Replay::from_bytes(bytes).map_err(|e| JsValue::from_str(&e.to_string()))?;
+
+    serde_wasm_bindgen::to_value(&replay).map_err(JsValue::from)
+}
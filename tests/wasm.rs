@@ -0,0 +1,36 @@
+#![cfg(all(target_arch = "wasm32", feature = "wasm"))]
+
+use osu_replay_parser::replay::Replay;
+use osu_replay_parser::wasm::parse_replay;
+use wasm_bindgen::JsValue;
+use wasm_bindgen_test::*;
+
+const TEST_REPLAY: &[u8] = include_bytes!("../assets/examples/replay-test.osr");
+
+#[wasm_bindgen_test]
+fn parse_replay_bytes() {
+    let replay = parse_replay(TEST_REPLAY).unwrap();
+
+    let player_name = js_sys::Reflect::get(&replay, &JsValue::from_str("player_name")).unwrap();
+
+    assert_eq!(player_name.as_string().unwrap(), "Sailor SnoW");
+}
+
+#[wasm_bindgen_test]
+fn parse_invalid_bytes() {
+    assert!(parse_replay(&[0xFF]).is_err());
+}
+
+#[wasm_bindgen_test]
+fn write_replay_round_trip() {
+    let replay = Replay::from_bytes(TEST_REPLAY).unwrap();
+
+    let written = Replay::from_bytes(&replay.to_osr_bytes().unwrap()).unwrap();
+
+    assert_eq!(written.player_name, replay.player_name);
+    assert_eq!(written.replay_data.seed, replay.replay_data.seed);
+    assert_eq!(
+        written.replay_data.frames.len(),
+        replay.replay_data.frames.len()
+    );
+}